@@ -13,22 +13,25 @@
 use ownable::traits::IntoOwned;
 use ownable::IntoOwned;
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
+pub use crate::span::{Span, Spanned};
+
 /// Represents a reserved or extensions range in `.proto` syntax.
 ///
+/// Finite ranges are half-open: `5` is stored as `5..6` and `5 to 9` as `5..10`.
+///
 /// # Examples
 /// ```rust
 /// use protobuf_ast_parser::ast::Range;
 ///
-/// let finite = Range::from(1..5);
+/// let finite = Range::from(1..5); // `1 to 4`
 /// let open_ended = Range::from(10..);
 /// ```
 #[derive(Debug, Clone, PartialEq)]
 pub enum Range {
-    Default(std::ops::Range<i64>),
-    From(std::ops::RangeFrom<i64>),
+    Default(std::ops::Range<i128>),
+    From(std::ops::RangeFrom<i128>),
 }
 
 impl IntoOwned for Range {
@@ -39,20 +42,106 @@ impl IntoOwned for Range {
     }
 }
 
-impl From<std::ops::Range<i64>> for Range {
-    fn from(range: std::ops::Range<i64>) -> Self {
+impl From<std::ops::Range<i128>> for Range {
+    fn from(range: std::ops::Range<i128>) -> Self {
         Self::Default(range)
     }
 }
 
-impl From<std::ops::RangeFrom<i64>> for Range {
-    fn from(range: std::ops::RangeFrom<i64>) -> Self {
+impl From<std::ops::RangeFrom<i128>> for Range {
+    fn from(range: std::ops::RangeFrom<i128>) -> Self {
         Self::From(range)
     }
 }
 
+/// String literal with both its source text and decoded value.
+///
+/// Adjacent literals (`"foo" "bar"`) are concatenated into a single value, while `raw` keeps
+/// the exact source text, quotes and separators included.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::StringLiteral;
+///
+/// let literal = StringLiteral::from("line\nbreak");
+/// assert_eq!(literal.raw, "\"line\\nbreak\"");
+/// assert_eq!(literal.as_str(), Some("line\nbreak"));
+/// ```
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub struct StringLiteral<'a> {
+    pub raw: Cow<'a, str>,
+    pub value: Cow<'a, [u8]>,
+}
+
+impl<'a> StringLiteral<'a> {
+    pub fn new(raw: &'a str, value: Cow<'a, [u8]>) -> Self {
+        Self {
+            raw: Cow::from(raw),
+            value,
+        }
+    }
+
+    /// Builds a literal from decoded bytes, quoting and escaping them for `raw`.
+    pub fn from_bytes(value: &'a [u8]) -> Self {
+        let mut raw = String::with_capacity(value.len() + 2);
+        raw.push('"');
+
+        for chunk in value.utf8_chunks() {
+            for ch in chunk.valid().chars() {
+                match ch {
+                    '"' => raw.push_str("\\\""),
+                    '\\' => raw.push_str("\\\\"),
+                    '\n' => raw.push_str("\\n"),
+                    '\r' => raw.push_str("\\r"),
+                    '\t' => raw.push_str("\\t"),
+                    ch if ch.is_control() => {
+                        let mut buffer = [0; 4];
+                        for byte in ch.encode_utf8(&mut buffer).bytes() {
+                            raw.push_str(&format!("\\{:03o}", byte));
+                        }
+                    }
+                    ch => raw.push(ch),
+                }
+            }
+
+            for byte in chunk.invalid() {
+                raw.push_str(&format!("\\{:03o}", byte));
+            }
+        }
+
+        raw.push('"');
+
+        Self {
+            raw: Cow::Owned(raw),
+            value: Cow::Borrowed(value),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Returns the decoded value if it is valid UTF-8.
+    pub fn as_str(&self) -> std::option::Option<&str> {
+        std::str::from_utf8(&self.value).ok()
+    }
+
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.value)
+    }
+}
+
+impl<'a> From<&'a str> for StringLiteral<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::from_bytes(value.as_bytes())
+    }
+}
+
 /// Option values and literal constants that can appear in `.proto` files.
 ///
+/// Integers are stored as `i128` so that every literal protobuf accepts, from
+/// `-9223372036854775808` up to `18446744073709551615`, is kept without loss.
+///
 /// # Examples
 /// ```rust
 /// use protobuf_ast_parser::ast::{Map, MapValue};
@@ -64,10 +153,13 @@ impl From<std::ops::RangeFrom<i64>> for Range {
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub enum MapValue<'a> {
     Boolean(bool),
-    Integer(i64),
+    Integer(i128),
+    Float(f64),
     Ident(Cow<'a, str>),
-    String(Cow<'a, str>),
+    String(StringLiteral<'a>),
     Map(Map<'a>),
+    /// `[a, b, c]` list of a repeated field inside a [`Map`].
+    List(Vec<MapValue<'a>>),
 }
 
 impl<'a> From<bool> for MapValue<'a> {
@@ -76,9 +168,33 @@ impl<'a> From<bool> for MapValue<'a> {
     }
 }
 
+impl<'a> From<i128> for MapValue<'a> {
+    fn from(value: i128) -> Self {
+        Self::Integer(value)
+    }
+}
+
 impl<'a> From<i64> for MapValue<'a> {
     fn from(value: i64) -> Self {
-        Self::Integer(value)
+        Self::Integer(value.into())
+    }
+}
+
+impl<'a> From<u64> for MapValue<'a> {
+    fn from(value: u64) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl<'a> From<f64> for MapValue<'a> {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl<'a> From<StringLiteral<'a>> for MapValue<'a> {
+    fn from(value: StringLiteral<'a>) -> Self {
+        Self::String(value)
     }
 }
 
@@ -88,8 +204,124 @@ impl<'a> From<Map<'a>> for MapValue<'a> {
     }
 }
 
-/// Map literal used by options and aggregate constants.
-pub type Map<'a> = HashMap<Cow<'a, str>, MapValue<'a>>;
+impl<'a> From<Vec<MapValue<'a>>> for MapValue<'a> {
+    fn from(value: Vec<MapValue<'a>>) -> Self {
+        Self::List(value)
+    }
+}
+
+/// Message literal used by options and aggregate constants.
+///
+/// Entries keep the order they were written in, and repeated keys (used for repeated fields)
+/// are all kept. Extension and `Any` keys are stored as written, brackets included (e.g.
+/// `[my.ext]` or `[type.googleapis.com/pkg.Msg]`).
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::{Map, MapValue};
+/// use std::borrow::Cow;
+///
+/// let map = Map::from([
+///     (Cow::from("path"), MapValue::Integer(1)),
+///     (Cow::from("span"), MapValue::Integer(2)),
+///     (Cow::from("path"), MapValue::Integer(3)),
+/// ]);
+/// assert_eq!(map.get("path"), Some(&MapValue::Integer(1)));
+/// assert_eq!(map.get_all("path").count(), 2);
+/// assert_eq!(map.keys().collect::<Vec<_>>(), ["path", "span", "path"]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, IntoOwned)]
+pub struct Map<'a> {
+    entries: Vec<(Cow<'a, str>, MapValue<'a>)>,
+}
+
+impl<'a> Map<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an entry, keeping any earlier entries with the same key.
+    pub fn push(&mut self, key: impl Into<Cow<'a, str>>, value: MapValue<'a>) {
+        self.entries.push((key.into(), value));
+    }
+
+    /// Returns the first value written for `key`.
+    pub fn get(&self, key: &str) -> std::option::Option<&MapValue<'a>> {
+        self.get_all(key).next()
+    }
+
+    /// Returns every value written for `key`, in source order.
+    pub fn get_all(&self, key: &str) -> impl Iterator<Item = &MapValue<'a>> {
+        self.entries
+            .iter()
+            .filter(move |(entry, _)| entry == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(key, _)| key.as_ref())
+    }
+}
+
+impl<'a> From<Vec<(Cow<'a, str>, MapValue<'a>)>> for Map<'a> {
+    fn from(entries: Vec<(Cow<'a, str>, MapValue<'a>)>) -> Self {
+        Self { entries }
+    }
+}
+
+impl<'a, const N: usize> From<[(Cow<'a, str>, MapValue<'a>); N]> for Map<'a> {
+    fn from(entries: [(Cow<'a, str>, MapValue<'a>); N]) -> Self {
+        Self::from(Vec::from(entries))
+    }
+}
+
+impl<'a> From<Map<'a>> for Vec<(Cow<'a, str>, MapValue<'a>)> {
+    fn from(value: Map<'a>) -> Self {
+        value.entries
+    }
+}
+
+impl<'a> FromIterator<(Cow<'a, str>, MapValue<'a>)> for Map<'a> {
+    fn from_iter<T: IntoIterator<Item = (Cow<'a, str>, MapValue<'a>)>>(iter: T) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'a> IntoIterator for Map<'a> {
+    type Item = (Cow<'a, str>, MapValue<'a>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'m, 'a> IntoIterator for &'m Map<'a> {
+    type Item = &'m (Cow<'a, str>, MapValue<'a>);
+    type IntoIter = std::slice::Iter<'m, (Cow<'a, str>, MapValue<'a>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl<'a> Deref for Map<'a> {
+    type Target = Vec<(Cow<'a, str>, MapValue<'a>)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl<'a> DerefMut for Map<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.entries
+    }
+}
 
 /// Helper for building a [`Map`] from borrowed keys.
 pub trait FromBorrowedIter<'a> {
@@ -111,32 +343,156 @@ impl<'a> FromBorrowedIter<'a> for Map<'a> {
 ///
 /// # Examples
 /// ```rust
-/// use protobuf_ast_parser::ast::{MapValue, Option};
+/// use protobuf_ast_parser::ast::{MapValue, Option, OptionNameSegment};
 ///
-/// let option = Option::new("deprecated", MapValue::from(true));
-/// assert_eq!(option.key, "deprecated");
+/// let option = Option::new("(validate.rules).string.min_len", MapValue::Integer(1));
+/// assert_eq!(option.name[0], OptionNameSegment::Extension("validate.rules".into()));
+/// assert_eq!(option.name.to_string(), "(validate.rules).string.min_len");
 /// ```
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct Option<'a> {
-    pub key: Cow<'a, str>,
+    pub name: OptionName<'a>,
     pub value: MapValue<'a>,
+    pub span: Span,
 }
 
 impl<'a> Option<'a> {
-    pub fn new(key: &'a str, value: MapValue<'a>) -> Self {
+    pub fn new(name: impl Into<OptionName<'a>>, value: MapValue<'a>) -> Self {
         Self {
-            key: Cow::from(key),
+            name: name.into(),
             value,
+            span: Span::default(),
+        }
+    }
+
+    /// Returns the feature path of an editions `features.*` option.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use protobuf_ast_parser::ast::{MapValue, Option, OptionNameSegment};
+    ///
+    /// let option = Option::new("features.field_presence", MapValue::from(true));
+    /// assert_eq!(
+    ///     option.feature(),
+    ///     Some(&[OptionNameSegment::Plain("field_presence".into())][..])
+    /// );
+    /// ```
+    pub fn feature(&self) -> std::option::Option<&[OptionNameSegment<'a>]> {
+        match self.name.split_first() {
+            Some((OptionNameSegment::Plain(first), rest)) if first == "features" => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+/// Dotted option name such as `deprecated` or `(my.ext).sub_field`.
+///
+/// Converting from a string splits it into segments, so hand-built names compare equal to
+/// parsed ones.
+#[derive(Debug, Default, Clone, PartialEq, IntoOwned)]
+pub struct OptionName<'a> {
+    segments: Vec<OptionNameSegment<'a>>,
+}
+
+impl<'a> OptionName<'a> {
+    /// Whether the name refers to an extension rather than a built-in option.
+    pub fn is_extension(&self) -> bool {
+        matches!(self.first(), Some(OptionNameSegment::Extension(_)))
+    }
+}
+
+impl<'a> From<Vec<OptionNameSegment<'a>>> for OptionName<'a> {
+    fn from(segments: Vec<OptionNameSegment<'a>>) -> Self {
+        Self { segments }
+    }
+}
+
+impl<'a> From<&'a str> for OptionName<'a> {
+    fn from(value: &'a str) -> Self {
+        let mut segments = vec![];
+        let mut rest = value;
+
+        while !rest.is_empty() {
+            let (segment, tail) = match rest.strip_prefix('(') {
+                Some(extension) => {
+                    let end = extension.find(')').unwrap_or(extension.len());
+                    let tail = extension.get(end + 1..).unwrap_or_default();
+
+                    (
+                        OptionNameSegment::Extension(Cow::from(&extension[..end])),
+                        tail,
+                    )
+                }
+                None => {
+                    let end = rest.find('.').unwrap_or(rest.len());
+                    (
+                        OptionNameSegment::Plain(Cow::from(&rest[..end])),
+                        &rest[end..],
+                    )
+                }
+            };
+
+            segments.push(segment);
+            rest = tail.strip_prefix('.').unwrap_or(tail);
         }
+
+        Self { segments }
+    }
+}
+
+impl<'a> From<OptionName<'a>> for Vec<OptionNameSegment<'a>> {
+    fn from(value: OptionName<'a>) -> Self {
+        value.segments
+    }
+}
+
+impl<'a> Deref for OptionName<'a> {
+    type Target = Vec<OptionNameSegment<'a>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.segments
+    }
+}
+
+impl<'a> DerefMut for OptionName<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.segments
+    }
+}
+
+impl<'a> std::fmt::Display for OptionName<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (index, segment) in self.segments.iter().enumerate() {
+            if index > 0 {
+                f.write_str(".")?;
+            }
+
+            match segment {
+                OptionNameSegment::Plain(name) => f.write_str(name)?,
+                OptionNameSegment::Extension(path) => write!(f, "({})", path)?,
+            }
+        }
+
+        Ok(())
     }
 }
 
+/// One dot-separated part of an [`OptionName`].
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub enum OptionNameSegment<'a> {
+    /// Field name, e.g. `deprecated`.
+    Plain(Cow<'a, str>),
+    /// Extension path written in parentheses, stored without them, e.g. `my.ext`.
+    Extension(Cow<'a, str>),
+}
+
 /// A parsed comment with both raw source and trimmed text.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct Comment<'a> {
     pub r#type: CommentType,
     pub source: Cow<'a, str>,
     pub text: Cow<'a, str>,
+    pub span: Span,
 }
 
 impl<'a> Comment<'a> {
@@ -145,6 +501,7 @@ impl<'a> Comment<'a> {
             r#type,
             text: Cow::from(text),
             source: Cow::from(source),
+            span: Span::default(),
         }
     }
 
@@ -153,6 +510,7 @@ impl<'a> Comment<'a> {
             r#type: CommentType::SingleLine,
             text: Cow::from(source[2..].trim()),
             source: Cow::from(source),
+            span: Span::default(),
         }
     }
 
@@ -161,10 +519,52 @@ impl<'a> Comment<'a> {
             r#type: CommentType::MultiLine,
             text: Cow::from(source[2..source.len() - 2].trim()),
             source: Cow::from(source),
+            span: Span::default(),
         }
     }
 }
 
+/// Comments attributed to a declaration the way `protoc` does for `SourceCodeInfo`.
+///
+/// Comment markers are stripped, line comments keep their trailing newline and leading `*`
+/// of block comment lines are removed. Parsed nodes also keep their comments as separate
+/// `Comment` entries; this only records which declaration each one documents.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::{ast, parse};
+///
+/// let source = "message User {\n  // Display name.\n  string name = 1; // Required.\n}";
+/// let ast = parse(source).unwrap();
+/// let ast::RootEntry::Message(message) = &ast[0] else { unreachable!() };
+/// let field = message.fields().next().unwrap();
+/// assert_eq!(field.comments.leading.as_deref(), Some(" Display name.\n"));
+/// assert_eq!(field.comments.trailing.as_deref(), Some(" Required.\n"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AttachedComments {
+    /// Comment directly above the declaration.
+    pub leading: std::option::Option<String>,
+    /// Comment after the declaration's `;` or opening `{`.
+    pub trailing: std::option::Option<String>,
+    /// Comment paragraphs above the declaration that are separated from it by a blank line.
+    pub leading_detached: Vec<String>,
+}
+
+impl AttachedComments {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_none() && self.trailing.is_none() && self.leading_detached.is_empty()
+    }
+}
+
+impl ownable::traits::IntoOwned for AttachedComments {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
+
 /// Comment type markers for single-line (`//`) and multi-line (`/* */`) comments.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub enum CommentType {
@@ -183,9 +583,9 @@ pub enum CommentType {
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub enum RootEntry<'a> {
     Comment(Comment<'a>),
-    Syntax(Cow<'a, str>),
-    Package(Cow<'a, str>),
-    Import(Cow<'a, str>),
+    Syntax(Syntax<'a>),
+    Package(Package<'a>),
+    Import(Import<'a>),
     Option(Option<'a>),
     Service(Service<'a>),
     Message(Message<'a>),
@@ -199,6 +599,24 @@ impl<'a> From<Comment<'a>> for RootEntry<'a> {
     }
 }
 
+impl<'a> From<Syntax<'a>> for RootEntry<'a> {
+    fn from(syntax: Syntax<'a>) -> Self {
+        Self::Syntax(syntax)
+    }
+}
+
+impl<'a> From<Package<'a>> for RootEntry<'a> {
+    fn from(package: Package<'a>) -> Self {
+        Self::Package(package)
+    }
+}
+
+impl<'a> From<Import<'a>> for RootEntry<'a> {
+    fn from(import: Import<'a>) -> Self {
+        Self::Import(import)
+    }
+}
+
 impl<'a> From<Option<'a>> for RootEntry<'a> {
     fn from(option: Option<'a>) -> Self {
         Self::Option(option)
@@ -232,11 +650,182 @@ impl<'a> From<Enum<'a>> for RootEntry<'a> {
 /// Alias for a full `.proto` file AST.
 pub type Root<'a> = Vec<RootEntry<'a>>;
 
+/// `syntax = "...";` or `edition = "...";` declaration.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::{Edition, Syntax};
+///
+/// assert_eq!(Syntax::new("proto3").version(), Some(Edition::Proto3));
+/// assert_eq!(Syntax::edition("2023").version(), Some(Edition::Edition2023));
+/// assert_eq!(Syntax::edition("proto3").version(), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub struct Syntax<'a> {
+    pub keyword: SyntaxKeyword,
+    pub value: StringLiteral<'a>,
+    pub span: Span,
+}
+
+impl<'a> Syntax<'a> {
+    /// Creates a `syntax = "...";` declaration.
+    pub fn new(value: impl Into<StringLiteral<'a>>) -> Self {
+        Self {
+            keyword: SyntaxKeyword::Syntax,
+            value: value.into(),
+            span: Span::default(),
+        }
+    }
+
+    /// Creates an `edition = "...";` declaration.
+    pub fn edition(value: impl Into<StringLiteral<'a>>) -> Self {
+        Self {
+            keyword: SyntaxKeyword::Edition,
+            ..Self::new(value)
+        }
+    }
+
+    /// Returns the declared language version, or `None` if the value is not recognized.
+    pub fn version(&self) -> std::option::Option<Edition> {
+        let value = self.value.as_str()?;
+
+        match self.keyword {
+            SyntaxKeyword::Syntax => Edition::from_syntax(value),
+            SyntaxKeyword::Edition => Edition::from_edition(value),
+        }
+    }
+}
+
+/// Keyword that introduced a [`Syntax`] declaration.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoOwned)]
+pub enum SyntaxKeyword {
+    #[default]
+    Syntax,
+    Edition,
+}
+
+/// Language version of a `.proto` file.
+///
+/// Files without a `syntax` or `edition` declaration are [`Edition::Proto2`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, IntoOwned)]
+pub enum Edition {
+    #[default]
+    Proto2,
+    Proto3,
+    Edition2023,
+    Edition2024,
+}
+
+impl Edition {
+    /// Parses the value of a `syntax` declaration.
+    pub fn from_syntax(value: &str) -> std::option::Option<Self> {
+        match value {
+            "proto2" => Some(Self::Proto2),
+            "proto3" => Some(Self::Proto3),
+            _ => None,
+        }
+    }
+
+    /// Parses the value of an `edition` declaration.
+    pub fn from_edition(value: &str) -> std::option::Option<Self> {
+        match value {
+            "2023" => Some(Self::Edition2023),
+            "2024" => Some(Self::Edition2024),
+            _ => None,
+        }
+    }
+
+    /// Returns the keyword used to declare this version.
+    pub fn keyword(&self) -> SyntaxKeyword {
+        match self {
+            Self::Proto2 | Self::Proto3 => SyntaxKeyword::Syntax,
+            Self::Edition2023 | Self::Edition2024 => SyntaxKeyword::Edition,
+        }
+    }
+
+    /// Returns the declared value, e.g. `proto3` or `2023`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Proto2 => "proto2",
+            Self::Proto3 => "proto3",
+            Self::Edition2023 => "2023",
+            Self::Edition2024 => "2024",
+        }
+    }
+
+    /// Whether this is an editions file, configured through `features` options.
+    pub fn is_edition(&self) -> bool {
+        self.keyword() == SyntaxKeyword::Edition
+    }
+}
+
+impl std::fmt::Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `package ...;` declaration.
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub struct Package<'a> {
+    pub path: Cow<'a, str>,
+    pub span: Span,
+}
+
+impl<'a> Package<'a> {
+    pub fn new(path: &'a str) -> Self {
+        Self {
+            path: Cow::from(path),
+            span: Span::default(),
+        }
+    }
+}
+
+/// `import [public | weak] "...";` declaration.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::{Import, ImportKind};
+///
+/// let import = Import::new(ImportKind::Public, "google/protobuf/any.proto");
+/// assert_eq!(import.path.as_str(), Some("google/protobuf/any.proto"));
+/// ```
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub struct Import<'a> {
+    pub kind: ImportKind,
+    pub path: StringLiteral<'a>,
+    pub span: Span,
+}
+
+impl<'a> Import<'a> {
+    pub fn new(kind: ImportKind, path: impl Into<StringLiteral<'a>>) -> Self {
+        Self {
+            kind,
+            path: path.into(),
+            span: Span::default(),
+        }
+    }
+}
+
+/// Import modifier keywords.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoOwned)]
+pub enum ImportKind {
+    /// Plain `import`: definitions are visible to the importing file only.
+    #[default]
+    Default,
+    /// `import public`: definitions are re-exported to files importing this one.
+    Public,
+    /// `import weak`: the imported file may be absent.
+    Weak,
+}
+
 /// Service definition with its RPC entries.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct Service<'a> {
     pub ident: Cow<'a, str>,
     pub entries: Vec<ServiceEntry<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
 }
 
 impl<'a> Service<'a> {
@@ -244,6 +833,8 @@ impl<'a> Service<'a> {
         Self {
             ident: Cow::from(ident),
             entries,
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
 }
@@ -276,6 +867,20 @@ impl<'a> From<Rpc<'a>> for ServiceEntry<'a> {
 }
 
 /// RPC definition inside a `service`.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::{MapValue, Option, Rpc, RpcEntry, RpcStream};
+///
+/// let rpc = Rpc::new(
+///     "Get",
+///     "Request",
+///     "Reply",
+///     RpcStream::None,
+///     vec![RpcEntry::from(Option::new("deprecated", MapValue::from(true)))],
+/// );
+/// assert_eq!(rpc.options().count(), 1);
+/// ```
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct Rpc<'a> {
     pub ident: Cow<'a, str>,
@@ -284,17 +889,57 @@ pub struct Rpc<'a> {
     pub reply: Cow<'a, str>,
 
     pub stream: RpcStream,
+    /// Body of `rpc ... { ... }`; empty for RPCs closed with `;`.
+    pub entries: Vec<RpcEntry<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
 }
 
 impl<'a> Rpc<'a> {
-    pub fn new(ident: &'a str, request: &'a str, reply: &'a str, stream: RpcStream) -> Self {
+    pub fn new(
+        ident: &'a str,
+        request: &'a str,
+        reply: &'a str,
+        stream: RpcStream,
+        entries: Vec<RpcEntry<'a>>,
+    ) -> Self {
         Self {
             ident: Cow::from(ident),
             request: Cow::from(request),
             reply: Cow::from(reply),
             stream,
+            entries,
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
+
+    /// Iterates over the `option` statements of the RPC body.
+    pub fn options(&self) -> impl Iterator<Item = &Option<'a>> {
+        self.entries.iter().filter_map(|entry| match entry {
+            RpcEntry::Option(option) => Some(option),
+            _ => None,
+        })
+    }
+}
+
+/// Entries that can appear inside an `rpc` body.
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub enum RpcEntry<'a> {
+    Comment(Comment<'a>),
+    Option(Option<'a>),
+}
+
+impl<'a> From<Comment<'a>> for RpcEntry<'a> {
+    fn from(comment: Comment<'a>) -> Self {
+        Self::Comment(comment)
+    }
+}
+
+impl<'a> From<Option<'a>> for RpcEntry<'a> {
+    fn from(option: Option<'a>) -> Self {
+        Self::Option(option)
+    }
 }
 
 /// Streaming mode for an RPC definition.
@@ -322,6 +967,8 @@ impl RpcStream {
 pub struct Message<'a> {
     pub ident: Cow<'a, str>,
     pub entries: Vec<MessageEntry<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
 }
 
 impl<'a> Message<'a> {
@@ -329,6 +976,8 @@ impl<'a> Message<'a> {
         Self {
             ident: Cow::from(ident),
             entries,
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
 
@@ -336,22 +985,39 @@ impl<'a> Message<'a> {
         Self {
             ident: Cow::from(ident),
             entries: vec![],
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
+
+    /// Iterates over the plain fields declared directly in the message.
+    pub fn fields(&self) -> impl Iterator<Item = &Field<'a>> {
+        self.entries.iter().filter_map(|entry| match entry {
+            MessageEntry::Field(field) => Some(field),
+            _ => None,
+        })
+    }
 }
 
+/// `reserved` field numbers of a message or values of an enum.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
-pub struct ReservedIndices(Vec<Range>);
+pub struct ReservedIndices {
+    ranges: Vec<Range>,
+    pub span: Span,
+}
 
 impl From<Vec<Range>> for ReservedIndices {
-    fn from(value: Vec<Range>) -> Self {
-        ReservedIndices(value)
+    fn from(ranges: Vec<Range>) -> Self {
+        Self {
+            ranges,
+            span: Span::default(),
+        }
     }
 }
 
-impl Into<Vec<Range>> for ReservedIndices {
-    fn into(self) -> Vec<Range> {
-        self.0
+impl From<ReservedIndices> for Vec<Range> {
+    fn from(value: ReservedIndices) -> Self {
+        value.ranges
     }
 }
 
@@ -359,63 +1025,81 @@ impl Deref for ReservedIndices {
     type Target = Vec<Range>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.ranges
     }
 }
 
 impl DerefMut for ReservedIndices {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.ranges
     }
 }
 
+/// `reserved` field names of a message or value names of an enum.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
-pub struct ReservedIdents<'a>(Vec<Cow<'a, str>>);
+pub struct ReservedIdents<'a> {
+    idents: Vec<StringLiteral<'a>>,
+    pub span: Span,
+}
 
 impl<'a> From<Vec<&'a str>> for ReservedIdents<'a> {
     fn from(value: Vec<&'a str>) -> Self {
-        ReservedIdents(value.iter().map(|s| Cow::from(*s)).collect())
+        Self::from(
+            value
+                .into_iter()
+                .map(StringLiteral::from)
+                .collect::<Vec<_>>(),
+        )
     }
 }
 
-impl<'a> From<Vec<Cow<'a, str>>> for ReservedIdents<'a> {
-    fn from(value: Vec<Cow<'a, str>>) -> Self {
-        ReservedIdents(value)
+impl<'a> From<Vec<StringLiteral<'a>>> for ReservedIdents<'a> {
+    fn from(idents: Vec<StringLiteral<'a>>) -> Self {
+        Self {
+            idents,
+            span: Span::default(),
+        }
     }
 }
 
-impl<'a> Into<Vec<Cow<'a, str>>> for ReservedIdents<'a> {
-    fn into(self) -> Vec<Cow<'a, str>> {
-        self.0
+impl<'a> From<ReservedIdents<'a>> for Vec<StringLiteral<'a>> {
+    fn from(value: ReservedIdents<'a>) -> Self {
+        value.idents
     }
 }
 
 impl<'a> Deref for ReservedIdents<'a> {
-    type Target = Vec<Cow<'a, str>>;
+    type Target = Vec<StringLiteral<'a>>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.idents
     }
 }
 
 impl<'a> DerefMut for ReservedIdents<'a> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.idents
     }
 }
 
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
-pub struct Extensions(Vec<Range>);
+pub struct Extensions {
+    ranges: Vec<Range>,
+    pub span: Span,
+}
 
 impl From<Vec<Range>> for Extensions {
-    fn from(value: Vec<Range>) -> Self {
-        Extensions(value)
+    fn from(ranges: Vec<Range>) -> Self {
+        Self {
+            ranges,
+            span: Span::default(),
+        }
     }
 }
 
-impl Into<Vec<Range>> for Extensions {
-    fn into(self) -> Vec<Range> {
-        self.0
+impl From<Extensions> for Vec<Range> {
+    fn from(value: Extensions) -> Self {
+        value.ranges
     }
 }
 
@@ -423,13 +1107,13 @@ impl Deref for Extensions {
     type Target = Vec<Range>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.ranges
     }
 }
 
 impl DerefMut for Extensions {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.ranges
     }
 }
 
@@ -440,6 +1124,7 @@ pub enum MessageEntry<'a> {
     Option(Option<'a>),
 
     Field(Field<'a>),
+    Group(Group<'a>),
     OneOf(OneOf<'a>),
     Message(Message<'a>),
     Extend(Extend<'a>),
@@ -469,6 +1154,12 @@ impl<'a> From<Field<'a>> for MessageEntry<'a> {
     }
 }
 
+impl<'a> From<Group<'a>> for MessageEntry<'a> {
+    fn from(group: Group<'a>) -> Self {
+        Self::Group(group)
+    }
+}
+
 impl<'a> From<OneOf<'a>> for MessageEntry<'a> {
     fn from(one_of: OneOf<'a>) -> Self {
         Self::OneOf(one_of)
@@ -523,28 +1214,77 @@ impl<'a> From<Extensions> for MessageEntry<'a> {
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct Field<'a> {
     pub modifier: std::option::Option<FieldModifier>,
-    pub r#type: Cow<'a, str>,
+    pub r#type: FieldType<'a>,
     pub ident: Cow<'a, str>,
-    pub index: i64,
+    pub index: i128,
     pub options: Vec<Option<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
 }
 
 impl<'a> Field<'a> {
     pub fn new(
         modifier: std::option::Option<FieldModifier>,
-        r#type: &'a str,
+        r#type: impl Into<FieldType<'a>>,
         ident: &'a str,
-        index: i64,
+        index: i128,
         options: Vec<Option<'a>>,
     ) -> Self {
         Self {
             modifier,
-            r#type: Cow::from(r#type),
+            r#type: r#type.into(),
+            ident: Cow::from(ident),
+            index,
+            options,
+            comments: AttachedComments::default(),
+            span: Span::default(),
+        }
+    }
+}
+
+/// proto2 `group`: a field together with the nested message type it holds.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::{FieldModifier, Group};
+///
+/// let group = Group::new(Some(FieldModifier::Repeated), "Result", 1, vec![], vec![]);
+/// assert_eq!(group.field_ident(), "result");
+/// ```
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub struct Group<'a> {
+    pub modifier: std::option::Option<FieldModifier>,
+    pub ident: Cow<'a, str>,
+    pub index: i128,
+    pub options: Vec<Option<'a>>,
+    pub entries: Vec<MessageEntry<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
+}
+
+impl<'a> Group<'a> {
+    pub fn new(
+        modifier: std::option::Option<FieldModifier>,
+        ident: &'a str,
+        index: i128,
+        options: Vec<Option<'a>>,
+        entries: Vec<MessageEntry<'a>>,
+    ) -> Self {
+        Self {
+            modifier,
             ident: Cow::from(ident),
             index,
             options,
+            entries,
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
+
+    /// Name of the generated field, which is the group name in lowercase.
+    pub fn field_ident(&self) -> String {
+        self.ident.to_lowercase()
+    }
 }
 
 /// `oneof` definition inside a message.
@@ -552,6 +1292,8 @@ impl<'a> Field<'a> {
 pub struct OneOf<'a> {
     pub ident: Cow<'a, str>,
     pub entries: Vec<OneOfEntry<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
 }
 
 impl<'a> OneOf<'a> {
@@ -559,6 +1301,8 @@ impl<'a> OneOf<'a> {
         Self {
             ident: Cow::from(ident),
             entries,
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
 }
@@ -570,6 +1314,7 @@ pub enum OneOfEntry<'a> {
     Option(Option<'a>),
 
     Field(Field<'a>),
+    Group(Group<'a>),
 }
 
 impl<'a> From<Comment<'a>> for OneOfEntry<'a> {
@@ -590,6 +1335,12 @@ impl<'a> From<Field<'a>> for OneOfEntry<'a> {
     }
 }
 
+impl<'a> From<Group<'a>> for OneOfEntry<'a> {
+    fn from(group: Group<'a>) -> Self {
+        Self::Group(group)
+    }
+}
+
 /// Field modifier keywords.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub enum FieldModifier {
@@ -598,11 +1349,241 @@ pub enum FieldModifier {
     Repeated,
 }
 
+/// Type of a [`Field`].
+///
+/// Converting from a string recognizes scalar names and `map<K, V>`; anything else is a
+/// named reference.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::{FieldType, ScalarType};
+///
+/// let r#type = FieldType::from("map<string, pkg.Value>");
+/// assert_eq!(
+///     r#type,
+///     FieldType::Map {
+///         key: Box::new(FieldType::Scalar(ScalarType::String)),
+///         value: Box::new(FieldType::Relative("pkg.Value".into())),
+///     }
+/// );
+/// assert_eq!(r#type.to_string(), "map<string, pkg.Value>");
+/// ```
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub enum FieldType<'a> {
+    /// Built-in scalar such as `int32` or `string`.
+    Scalar(ScalarType),
+    /// Fully-qualified message or enum reference (`.pkg.Foo`), stored without the leading
+    /// period.
+    Absolute(Cow<'a, str>),
+    /// Message or enum reference resolved relative to the enclosing scope (`Foo`, `pkg.Foo`).
+    Relative(Cow<'a, str>),
+    /// `map<key, value>`; a valid key is an integral, `bool` or `string` scalar.
+    Map {
+        key: Box<FieldType<'a>>,
+        value: Box<FieldType<'a>>,
+    },
+}
+
+impl<'a> FieldType<'a> {
+    /// Resolves a single type name, which is a scalar unless it names a message or enum.
+    pub fn from_name(name: &'a str) -> Self {
+        match ScalarType::from_name(name) {
+            Some(scalar) => Self::Scalar(scalar),
+            None => match name.strip_prefix('.') {
+                Some(path) => Self::Absolute(Cow::from(path)),
+                None => Self::Relative(Cow::from(name)),
+            },
+        }
+    }
+
+    /// Returns the scalar type, if this is a scalar field.
+    pub fn scalar(&self) -> std::option::Option<ScalarType> {
+        match self {
+            Self::Scalar(scalar) => Some(*scalar),
+            _ => None,
+        }
+    }
+
+    pub fn is_map(&self) -> bool {
+        matches!(self, Self::Map { .. })
+    }
+
+    pub fn map(key: FieldType<'a>, value: FieldType<'a>) -> Self {
+        Self::Map {
+            key: Box::new(key),
+            value: Box::new(value),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for FieldType<'a> {
+    fn from(value: &'a str) -> Self {
+        let map = value
+            .strip_prefix("map")
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix('<')?.strip_suffix('>'))
+            .and_then(|rest| rest.split_once(','));
+
+        match map {
+            Some((key, value)) => {
+                Self::map(Self::from_name(key.trim()), Self::from_name(value.trim()))
+            }
+            None => Self::from_name(value),
+        }
+    }
+}
+
+impl<'a> From<ScalarType> for FieldType<'a> {
+    fn from(value: ScalarType) -> Self {
+        Self::Scalar(value)
+    }
+}
+
+impl<'a> std::fmt::Display for FieldType<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Scalar(scalar) => write!(f, "{}", scalar),
+            Self::Absolute(path) => write!(f, ".{}", path),
+            Self::Relative(path) => f.write_str(path),
+            Self::Map { key, value } => write!(f, "map<{}, {}>", key, value),
+        }
+    }
+}
+
+/// Built-in scalar value types.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::ast::{MapValue, ScalarType, WireType};
+///
+/// let scalar = ScalarType::from_name("sint64").unwrap();
+/// assert_eq!(scalar.wire_type(), WireType::Varint);
+/// assert_eq!(scalar.default_value(), MapValue::Integer(0));
+/// assert!(scalar.is_packable() && scalar.is_valid_map_key());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoOwned)]
+pub enum ScalarType {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+}
+
+impl ScalarType {
+    pub fn from_name(name: &str) -> std::option::Option<Self> {
+        let scalar = match name {
+            "double" => Self::Double,
+            "float" => Self::Float,
+            "int32" => Self::Int32,
+            "int64" => Self::Int64,
+            "uint32" => Self::Uint32,
+            "uint64" => Self::Uint64,
+            "sint32" => Self::Sint32,
+            "sint64" => Self::Sint64,
+            "fixed32" => Self::Fixed32,
+            "fixed64" => Self::Fixed64,
+            "sfixed32" => Self::Sfixed32,
+            "sfixed64" => Self::Sfixed64,
+            "bool" => Self::Bool,
+            "string" => Self::String,
+            "bytes" => Self::Bytes,
+            _ => return None,
+        };
+
+        Some(scalar)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Double => "double",
+            Self::Float => "float",
+            Self::Int32 => "int32",
+            Self::Int64 => "int64",
+            Self::Uint32 => "uint32",
+            Self::Uint64 => "uint64",
+            Self::Sint32 => "sint32",
+            Self::Sint64 => "sint64",
+            Self::Fixed32 => "fixed32",
+            Self::Fixed64 => "fixed64",
+            Self::Sfixed32 => "sfixed32",
+            Self::Sfixed64 => "sfixed64",
+            Self::Bool => "bool",
+            Self::String => "string",
+            Self::Bytes => "bytes",
+        }
+    }
+
+    /// Whether the type may be used as a `map` key: any integral type, `bool` or `string`.
+    pub fn is_valid_map_key(&self) -> bool {
+        !matches!(self, Self::Double | Self::Float | Self::Bytes)
+    }
+
+    /// Whether `repeated` fields of this type are packed by default in proto3.
+    pub fn is_packable(&self) -> bool {
+        !matches!(self, Self::String | Self::Bytes)
+    }
+
+    /// Wire type used to encode a single value of this type.
+    pub fn wire_type(&self) -> WireType {
+        match self {
+            Self::Int32
+            | Self::Int64
+            | Self::Uint32
+            | Self::Uint64
+            | Self::Sint32
+            | Self::Sint64
+            | Self::Bool => WireType::Varint,
+            Self::Double | Self::Fixed64 | Self::Sfixed64 => WireType::Fixed64,
+            Self::Float | Self::Fixed32 | Self::Sfixed32 => WireType::Fixed32,
+            Self::String | Self::Bytes => WireType::LengthDelimited,
+        }
+    }
+
+    /// Value a field of this type has when it is not set.
+    pub fn default_value(&self) -> MapValue<'static> {
+        match self {
+            Self::Double | Self::Float => MapValue::Float(0.0),
+            Self::Bool => MapValue::Boolean(false),
+            Self::String | Self::Bytes => MapValue::String(StringLiteral::from("")),
+            _ => MapValue::Integer(0),
+        }
+    }
+}
+
+/// Protocol Buffers wire types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoOwned)]
+pub enum WireType {
+    Varint = 0,
+    Fixed64 = 1,
+    LengthDelimited = 2,
+    StartGroup = 3,
+    EndGroup = 4,
+    Fixed32 = 5,
+}
+
+impl std::fmt::Display for ScalarType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Extend block definition.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct Extend<'a> {
     pub r#type: Cow<'a, str>,
     pub entries: Vec<ExtendEntry<'a>>,
+    pub span: Span,
 }
 
 impl<'a> Extend<'a> {
@@ -610,6 +1591,7 @@ impl<'a> Extend<'a> {
         Self {
             r#type: Cow::from(r#type),
             entries,
+            span: Span::default(),
         }
     }
 }
@@ -619,6 +1601,7 @@ impl<'a> Extend<'a> {
 pub enum ExtendEntry<'a> {
     Comment(Comment<'a>),
     Field(Field<'a>),
+    Group(Group<'a>),
 }
 
 impl<'a> From<Comment<'a>> for ExtendEntry<'a> {
@@ -633,11 +1616,19 @@ impl<'a> From<Field<'a>> for ExtendEntry<'a> {
     }
 }
 
+impl<'a> From<Group<'a>> for ExtendEntry<'a> {
+    fn from(group: Group<'a>) -> Self {
+        Self::Group(group)
+    }
+}
+
 /// Enum definition.
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct Enum<'a> {
     pub ident: Cow<'a, str>,
     pub entries: Vec<EnumEntry<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
 }
 
 impl<'a> Enum<'a> {
@@ -645,6 +1636,8 @@ impl<'a> Enum<'a> {
         Self {
             ident: Cow::from(ident),
             entries,
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
 }
@@ -655,6 +1648,9 @@ pub enum EnumEntry<'a> {
     Comment(Comment<'a>),
     Option(Option<'a>),
     Variant(EnumVariant<'a>),
+
+    ReservedIndices(ReservedIndices),
+    ReservedIdents(ReservedIdents<'a>),
 }
 
 impl<'a> From<Comment<'a>> for EnumEntry<'a> {
@@ -675,6 +1671,18 @@ impl<'a> From<EnumVariant<'a>> for EnumEntry<'a> {
     }
 }
 
+impl<'a> From<ReservedIndices> for EnumEntry<'a> {
+    fn from(reserved_indices: ReservedIndices) -> Self {
+        Self::ReservedIndices(reserved_indices)
+    }
+}
+
+impl<'a> From<ReservedIdents<'a>> for EnumEntry<'a> {
+    fn from(reserved_idents: ReservedIdents<'a>) -> Self {
+        Self::ReservedIdents(reserved_idents)
+    }
+}
+
 /// Enum variant definition inside an enum block.
 ///
 /// # Examples
@@ -687,16 +1695,102 @@ impl<'a> From<EnumVariant<'a>> for EnumEntry<'a> {
 #[derive(Debug, Clone, PartialEq, IntoOwned)]
 pub struct EnumVariant<'a> {
     pub ident: Cow<'a, str>,
-    pub value: i64,
+    pub value: i128,
     pub options: Vec<Option<'a>>,
+    pub comments: AttachedComments,
+    pub span: Span,
 }
 
 impl<'a> EnumVariant<'a> {
-    pub fn new(ident: &'a str, value: i64, options: Vec<Option<'a>>) -> Self {
+    pub fn new(ident: &'a str, value: i128, options: Vec<Option<'a>>) -> Self {
         Self {
             ident: Cow::from(ident),
             value,
             options,
+            comments: AttachedComments::default(),
+            span: Span::default(),
         }
     }
 }
+
+macro_rules! impl_spanned {
+    ($($node:ty),* $(,)?) => {
+        $(
+            impl Spanned for $node {
+                fn span(&self) -> Span {
+                    self.span
+                }
+
+                fn span_mut(&mut self) -> &mut Span {
+                    &mut self.span
+                }
+            }
+        )*
+    };
+}
+
+impl_spanned!(
+    Option<'_>,
+    Comment<'_>,
+    Syntax<'_>,
+    Package<'_>,
+    Import<'_>,
+    Service<'_>,
+    Rpc<'_>,
+    Message<'_>,
+    ReservedIndices,
+    ReservedIdents<'_>,
+    Extensions,
+    Field<'_>,
+    Group<'_>,
+    OneOf<'_>,
+    Extend<'_>,
+    Enum<'_>,
+    EnumVariant<'_>,
+);
+
+macro_rules! impl_spanned_entry {
+    ($entry:ident { $($variant:ident),* $(,)? }) => {
+        impl Spanned for $entry<'_> {
+            fn span(&self) -> Span {
+                match self {
+                    $(Self::$variant(node) => node.span(),)*
+                }
+            }
+
+            fn span_mut(&mut self) -> &mut Span {
+                match self {
+                    $(Self::$variant(node) => node.span_mut(),)*
+                }
+            }
+        }
+    };
+}
+
+impl_spanned_entry!(RootEntry {
+    Comment, Syntax, Package, Import, Option, Service, Message, Extend, Enum
+});
+impl_spanned_entry!(ServiceEntry { Comment, Option, Rpc });
+impl_spanned_entry!(RpcEntry { Comment, Option });
+impl_spanned_entry!(MessageEntry {
+    Comment,
+    Option,
+    Field,
+    Group,
+    OneOf,
+    Message,
+    Extend,
+    Enum,
+    ReservedIndices,
+    ReservedIdents,
+    Extensions,
+});
+impl_spanned_entry!(OneOfEntry { Comment, Option, Field, Group });
+impl_spanned_entry!(ExtendEntry { Comment, Field, Group });
+impl_spanned_entry!(EnumEntry {
+    Comment,
+    Option,
+    Variant,
+    ReservedIndices,
+    ReservedIdents,
+});
@@ -0,0 +1,436 @@
+//! Attribution of comments to declarations, following the rules `protoc` uses to fill
+//! `SourceCodeInfo`.
+//!
+//! Every gap between two tokens is scanned the same way the `protoc` tokenizer does: a
+//! comment on the line of the previous token trails it, a comment block directly above the
+//! next token leads it, and blocks separated by blank lines are detached.
+
+use crate::ast::{self, AttachedComments};
+use crate::lexer::{self, Token};
+use crate::span::Span;
+
+/// Fills [`AttachedComments`] of every declaration in `root`.
+pub(crate) fn attach(source: &str, root: &mut ast::Root) {
+    let tokens: Vec<Span> = lexer::Lexer::new(source)
+        .filter_map(|token| match token {
+            Ok((_, Token::SingleLineComment(_) | Token::MultiLineComment(_), _)) => None,
+            Ok((start, _, end)) => Some(Span::new(start, end)),
+            Err(error) => Some(error.span()),
+        })
+        .collect();
+
+    // `gaps[i]` holds the comments between token `i - 1` and token `i`; the last one holds
+    // the comments after the final token.
+    let gaps = (0..=tokens.len())
+        .map(|index| {
+            let start = index.checked_sub(1).map(|prev| tokens[prev].end);
+            let next = tokens.get(index).map(|next| &source[next.range()]);
+            let end = tokens.get(index).map_or(source.len(), |next| next.start);
+
+            scan_gap(&source[start.unwrap_or(0)..end], start.is_none(), next)
+        })
+        .collect();
+
+    let attacher = Attacher {
+        source,
+        tokens,
+        gaps,
+    };
+
+    for entry in root {
+        match entry {
+            ast::RootEntry::Service(service) => attacher.service(service),
+            ast::RootEntry::Message(message) => attacher.message(message),
+            ast::RootEntry::Extend(extend) => attacher.extend(extend),
+            ast::RootEntry::Enum(r#enum) => attacher.r#enum(r#enum),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the text `protoc` records for a `/* ... */` comment.
+pub(crate) fn block_comment_text(source: &str) -> String {
+    let mut cursor = Cursor {
+        text: source,
+        pos: 2,
+        line: 0,
+    };
+    let mut text = String::new();
+
+    cursor.block_comment(&mut text);
+    text
+}
+
+/// Comments found between two tokens.
+#[derive(Debug, Default)]
+struct Gap {
+    /// Trailing comment of the previous token.
+    trailing: Option<String>,
+    detached: Vec<String>,
+    /// Leading comment of the next token.
+    leading: Option<String>,
+}
+
+struct Attacher<'s> {
+    source: &'s str,
+    tokens: Vec<Span>,
+    gaps: Vec<Gap>,
+}
+
+impl Attacher<'_> {
+    /// Leading comments come before the first token of `span`, trailing comments after the
+    /// `;` or `{` that ends its header.
+    fn comments(&self, span: Span) -> AttachedComments {
+        let Ok(first) = self
+            .tokens
+            .binary_search_by_key(&span.start, |token| token.start)
+        else {
+            return AttachedComments::default();
+        };
+
+        let mut depth = 0usize;
+        let header_end = self.tokens[first..]
+            .iter()
+            .take_while(|token| token.start < span.end)
+            .position(|token| match &self.source[token.range()] {
+                "(" | "[" => {
+                    depth += 1;
+                    false
+                }
+                ")" | "]" => {
+                    depth = depth.saturating_sub(1);
+                    false
+                }
+                "{" | ";" => depth == 0,
+                _ => false,
+            });
+
+        AttachedComments {
+            leading: self.gaps[first].leading.clone(),
+            trailing: header_end.and_then(|end| self.gaps[first + end + 1].trailing.clone()),
+            leading_detached: self.gaps[first].detached.clone(),
+        }
+    }
+
+    fn service(&self, service: &mut ast::Service) {
+        service.comments = self.comments(service.span);
+
+        for entry in &mut service.entries {
+            if let ast::ServiceEntry::Rpc(rpc) = entry {
+                rpc.comments = self.comments(rpc.span);
+            }
+        }
+    }
+
+    fn message(&self, message: &mut ast::Message) {
+        message.comments = self.comments(message.span);
+        self.message_entries(&mut message.entries);
+    }
+
+    fn message_entries(&self, entries: &mut [ast::MessageEntry]) {
+        for entry in entries {
+            match entry {
+                ast::MessageEntry::Field(field) => self.field(field),
+                ast::MessageEntry::Group(group) => self.group(group),
+                ast::MessageEntry::OneOf(one_of) => self.one_of(one_of),
+                ast::MessageEntry::Message(message) => self.message(message),
+                ast::MessageEntry::Extend(extend) => self.extend(extend),
+                ast::MessageEntry::Enum(r#enum) => self.r#enum(r#enum),
+                _ => {}
+            }
+        }
+    }
+
+    fn field(&self, field: &mut ast::Field) {
+        field.comments = self.comments(field.span);
+    }
+
+    fn group(&self, group: &mut ast::Group) {
+        group.comments = self.comments(group.span);
+        self.message_entries(&mut group.entries);
+    }
+
+    fn one_of(&self, one_of: &mut ast::OneOf) {
+        one_of.comments = self.comments(one_of.span);
+
+        for entry in &mut one_of.entries {
+            match entry {
+                ast::OneOfEntry::Field(field) => self.field(field),
+                ast::OneOfEntry::Group(group) => self.group(group),
+                _ => {}
+            }
+        }
+    }
+
+    fn extend(&self, extend: &mut ast::Extend) {
+        for entry in &mut extend.entries {
+            match entry {
+                ast::ExtendEntry::Field(field) => self.field(field),
+                ast::ExtendEntry::Group(group) => self.group(group),
+                _ => {}
+            }
+        }
+    }
+
+    fn r#enum(&self, r#enum: &mut ast::Enum) {
+        r#enum.comments = self.comments(r#enum.span);
+
+        for entry in &mut r#enum.entries {
+            if let ast::EnumEntry::Variant(variant) = entry {
+                variant.comments = self.comments(variant.span);
+            }
+        }
+    }
+}
+
+/// Splits the whitespace and comments between two tokens into trailing, detached and
+/// leading comments.
+fn scan_gap(text: &str, at_start: bool, next: Option<&str>) -> Gap {
+    let mut cursor = Cursor {
+        text,
+        pos: 0,
+        line: 0,
+    };
+    let mut collector = Collector::default();
+    let mut trailing_end_line = None;
+
+    if at_start {
+        collector.can_attach_to_prev = false;
+    } else {
+        collector.can_attach_to_prev = true;
+
+        // A comment on the same line as the previous token trails it.
+        cursor.skip_spaces();
+        match cursor.comment_start() {
+            Some(CommentStart::Line) => {
+                trailing_end_line = Some(cursor.line);
+                cursor.line_comment(collector.line_buffer());
+                collector.flush();
+            }
+            Some(CommentStart::Block) => {
+                cursor.block_comment(collector.block_buffer());
+                trailing_end_line = Some(cursor.line);
+                cursor.skip_spaces();
+
+                if !cursor.consume(b'\n') {
+                    // The next token is on the same line, so the comment belongs to neither.
+                    collector.clear_buffer();
+                    return collector.finish();
+                }
+
+                collector.flush();
+            }
+            None => {
+                if !cursor.consume(b'\n') {
+                    return collector.finish();
+                }
+            }
+        }
+    }
+
+    loop {
+        cursor.skip_spaces();
+
+        match cursor.comment_start() {
+            Some(CommentStart::Line) => cursor.line_comment(collector.line_buffer()),
+            Some(CommentStart::Block) => {
+                cursor.block_comment(collector.block_buffer());
+                cursor.skip_spaces();
+                cursor.consume(b'\n');
+            }
+            None if cursor.consume(b'\n') => {
+                // A blank line ends the current block and detaches it from the previous token.
+                collector.flush();
+                collector.can_attach_to_prev = false;
+            }
+            None => {
+                // A comment right before the end of a scope documents nothing that follows.
+                if next.is_none_or(|next| matches!(next, "}" | "]" | ")")) {
+                    collector.flush();
+                }
+
+                // With the next token on the line of the previous token or trailing comment,
+                // a lone comment is ambiguous and gets detached.
+                if next.is_some() && (cursor.line == 0 || trailing_end_line == Some(cursor.line)) {
+                    collector.maybe_detach();
+                }
+
+                return collector.finish();
+            }
+        }
+    }
+}
+
+enum CommentStart {
+    Line,
+    Block,
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+    /// Number of line breaks consumed so far.
+    line: usize,
+}
+
+impl Cursor<'_> {
+    fn skip_spaces(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len()
+            - rest
+                .trim_start_matches([' ', '\t', '\r', '\x0B', '\x0C'])
+                .len();
+    }
+
+    fn consume(&mut self, byte: u8) -> bool {
+        if self.text.as_bytes().get(self.pos) != Some(&byte) {
+            return false;
+        }
+
+        self.pos += 1;
+        if byte == b'\n' {
+            self.line += 1;
+        }
+
+        true
+    }
+
+    fn comment_start(&mut self) -> Option<CommentStart> {
+        let rest = &self.text[self.pos..];
+        let start = if rest.starts_with("//") {
+            CommentStart::Line
+        } else if rest.starts_with("/*") {
+            CommentStart::Block
+        } else {
+            return None;
+        };
+
+        self.pos += 2;
+        Some(start)
+    }
+
+    /// Records the comment up to and including the line break.
+    fn line_comment(&mut self, buffer: &mut String) {
+        let end = self.text[self.pos..]
+            .find('\n')
+            .map_or(self.text.len(), |index| self.pos + index + 1);
+
+        buffer.push_str(&self.text[self.pos..end]);
+        self.line += self.text[self.pos..end].ends_with('\n') as usize;
+        self.pos = end;
+    }
+
+    /// Records the comment body, dropping the indentation and `*` that start its lines.
+    fn block_comment(&mut self, buffer: &mut String) {
+        let mut start = self.pos;
+
+        loop {
+            let Some(index) = self.text[self.pos..].find(['\n', '*']) else {
+                buffer.push_str(&self.text[start..]);
+                self.pos = self.text.len();
+                return;
+            };
+            self.pos += index;
+
+            if self.consume(b'\n') {
+                buffer.push_str(&self.text[start..self.pos]);
+                self.skip_spaces();
+
+                if self.consume(b'*') && self.consume(b'/') {
+                    return;
+                }
+
+                start = self.pos;
+            } else if self.text[self.pos..].starts_with("*/") {
+                buffer.push_str(&self.text[start..self.pos]);
+                self.pos += 2;
+                return;
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+}
+
+/// Port of the `protoc` tokenizer's comment collector.
+#[derive(Default)]
+struct Collector {
+    gap: Gap,
+    buffer: String,
+    has_comment: bool,
+    is_line_comment: bool,
+    can_attach_to_prev: bool,
+    has_trailing: bool,
+    num_comments: usize,
+}
+
+impl Collector {
+    /// Consecutive line comments are merged, but never with a block comment.
+    fn line_buffer(&mut self) -> &mut String {
+        if self.has_comment && !self.is_line_comment {
+            self.flush();
+        }
+
+        self.has_comment = true;
+        self.is_line_comment = true;
+        &mut self.buffer
+    }
+
+    fn block_buffer(&mut self) -> &mut String {
+        if self.has_comment {
+            self.flush();
+        }
+
+        self.has_comment = true;
+        self.is_line_comment = false;
+        &mut self.buffer
+    }
+
+    fn clear_buffer(&mut self) {
+        self.buffer.clear();
+        self.has_comment = false;
+    }
+
+    /// Called once the buffered comment is known not to lead the next token.
+    fn flush(&mut self) {
+        if !self.has_comment {
+            return;
+        }
+
+        let comment = std::mem::take(&mut self.buffer);
+        if self.can_attach_to_prev {
+            self.gap.trailing = Some(comment);
+            self.has_trailing = true;
+            self.can_attach_to_prev = false;
+        } else {
+            self.gap.detached.push(comment);
+        }
+
+        self.clear_buffer();
+        self.num_comments += 1;
+    }
+
+    fn maybe_detach(&mut self) {
+        if self.num_comments + self.has_comment as usize != 1 {
+            return;
+        }
+
+        if self.has_trailing
+            && let Some(trailing) = self.gap.trailing.take()
+        {
+            self.gap.detached.insert(0, trailing);
+        }
+
+        self.can_attach_to_prev = false;
+        self.flush();
+    }
+
+    /// Whatever is still buffered leads the next token.
+    fn finish(mut self) -> Gap {
+        if self.has_comment {
+            self.gap.leading = Some(self.buffer);
+        }
+
+        self.gap
+    }
+}
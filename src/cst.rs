@@ -0,0 +1,649 @@
+//! Lossless concrete syntax tree.
+//!
+//! Every byte of the source is kept: whitespace and comments are stored as [`Trivia`] on the
+//! neighbouring [`Token`], and stray `;` become [`NodeKind::Empty`] statements. Printing a
+//! [`SyntaxTree`] with [`Display`](std::fmt::Display) reproduces the source exactly, so edited
+//! trees keep the untouched parts of a file byte-for-byte.
+//!
+//! Statements are grouped into nodes following the declarations [`parse_recovering`] reduces,
+//! so their spans match the spans of the corresponding AST nodes, which makes it easy to locate
+//! the text of a declaration found through the AST.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::{ast, cst, parse};
+//!
+//! let source = "syntax = \"proto3\";\n\nmessage User {\n  string name = 1; // Display name.\n}\n";
+//! let ast = parse(source).unwrap();
+//! let mut tree = cst::SyntaxTree::parse(source);
+//! assert_eq!(tree.to_string(), source);
+//!
+//! let ast::RootEntry::Message(message) = &ast[1] else { unreachable!() };
+//! let field = message.fields().next().unwrap();
+//! tree.find_mut(field.span).unwrap().replace("string full_name = 1;");
+//! assert_eq!(
+//!     tree.to_string(),
+//!     "syntax = \"proto3\";\n\nmessage User {\n  string full_name = 1; // Display name.\n}\n"
+//! );
+//! ```
+
+use crate::ast::{self, Spanned};
+use crate::lexer::{self, LexicalErrorKind};
+use crate::parse_recovering;
+use crate::span::Span;
+use crate::visit::{self, Scope, Visit};
+use std::borrow::Cow;
+
+/// Kinds of whitespace and comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    SingleLineComment,
+    MultiLineComment,
+}
+
+/// Whitespace or comment around a [`Token`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'a> {
+    pub kind: TriviaKind,
+    pub text: Cow<'a, str>,
+}
+
+impl<'a> Trivia<'a> {
+    pub fn new(kind: TriviaKind, text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+}
+
+/// What a [`Token`] holds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind<'a> {
+    Token(lexer::Token<'a>),
+    /// Input the lexer rejected.
+    Error(LexicalErrorKind),
+    /// Text inserted by an edit, kept as is.
+    Verbatim,
+}
+
+/// Token together with the trivia around it.
+///
+/// Trailing trivia holds everything up to the end of the token's line; the line break and
+/// anything after it lead the next token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub text: Cow<'a, str>,
+    pub leading: Vec<Trivia<'a>>,
+    pub trailing: Vec<Trivia<'a>>,
+    /// Location in the original source; [`Span::default`] for inserted tokens.
+    pub span: Span,
+}
+
+impl<'a> Token<'a> {
+    /// Builds an inserted token holding arbitrary source text.
+    pub fn verbatim(text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            kind: TokenKind::Verbatim,
+            text: text.into(),
+            leading: vec![],
+            trailing: vec![],
+            span: Span::default(),
+        }
+    }
+
+    /// Replaces the text of the token, keeping its trivia.
+    pub fn replace(&mut self, text: impl Into<Cow<'a, str>>) {
+        self.kind = TokenKind::Verbatim;
+        self.text = text.into();
+    }
+
+    /// Returns the lexed token, unless the token was edited or is invalid.
+    pub fn token(&self) -> Option<&lexer::Token<'a>> {
+        match &self.kind {
+            TokenKind::Token(token) => Some(token),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the comments around the token.
+    pub fn comments(&self) -> impl Iterator<Item = &Trivia<'a>> {
+        self.leading
+            .iter()
+            .chain(&self.trailing)
+            .filter(|trivia| trivia.kind != TriviaKind::Whitespace)
+    }
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.leading
+            .iter()
+            .try_for_each(|trivia| f.write_str(&trivia.text))?;
+        f.write_str(&self.text)?;
+        self.trailing
+            .iter()
+            .try_for_each(|trivia| f.write_str(&trivia.text))
+    }
+}
+
+/// Kinds of statements grouped into a [`Node`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    File,
+    Syntax,
+    Package,
+    Import,
+    Option,
+    Service,
+    Rpc,
+    Message,
+    Field,
+    Group,
+    OneOf,
+    Extend,
+    Enum,
+    EnumVariant,
+    Reserved,
+    Extensions,
+    /// Stray `;`.
+    Empty,
+    /// Tokens that do not start a statement, such as an unmatched `}`.
+    Unknown,
+}
+
+impl NodeKind {
+    /// Whether a `{` at the top level of the statement opens a body of nested statements.
+    fn has_body(&self) -> bool {
+        matches!(
+            self,
+            Self::Service
+                | Self::Rpc
+                | Self::Message
+                | Self::Group
+                | Self::OneOf
+                | Self::Extend
+                | Self::Enum
+        )
+    }
+}
+
+/// Child of a [`Node`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element<'a> {
+    Node(Node<'a>),
+    Token(Token<'a>),
+}
+
+impl std::fmt::Display for Element<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Node(node) => node.fmt(f),
+            Self::Token(token) => token.fmt(f),
+        }
+    }
+}
+
+/// Statement with its tokens and, for declarations with a body, nested statements.
+///
+/// Nested statements sit between the `{` and `}` tokens of the body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<'a> {
+    pub kind: NodeKind,
+    pub children: Vec<Element<'a>>,
+    /// Location in the original source, from the first to the last token.
+    pub span: Span,
+}
+
+impl<'a> Node<'a> {
+    pub fn new(kind: NodeKind, children: Vec<Element<'a>>) -> Self {
+        Self {
+            kind,
+            children,
+            span: Span::default(),
+        }
+    }
+
+    /// Iterates over the tokens of the node, nested nodes included.
+    pub fn tokens(&self) -> Box<dyn Iterator<Item = &Token<'a>> + '_> {
+        Box::new(self.children.iter().flat_map(|child| match child {
+            Element::Node(node) => node.tokens(),
+            Element::Token(token) => Box::new(std::iter::once(token)),
+        }))
+    }
+
+    /// Iterates over the nested statements.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node<'a>> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Node(node) => Some(node),
+            Element::Token(_) => None,
+        })
+    }
+
+    /// Finds the statement with the given span, such as the span of an AST node.
+    pub fn find(&self, span: Span) -> Option<&Node<'a>> {
+        if self.span == span && self.kind != NodeKind::File {
+            return Some(self);
+        }
+
+        self.nodes()
+            .filter(|node| node.span.start <= span.start && span.end <= node.span.end)
+            .find_map(|node| node.find(span))
+    }
+
+    pub fn find_mut(&mut self, span: Span) -> Option<&mut Node<'a>> {
+        if self.span == span && self.kind != NodeKind::File {
+            return Some(self);
+        }
+
+        self.children
+            .iter_mut()
+            .filter_map(|child| match child {
+                Element::Node(node) => Some(node),
+                Element::Token(_) => None,
+            })
+            .filter(|node| node.span.start <= span.start && span.end <= node.span.end)
+            .find_map(|node| node.find_mut(span))
+    }
+
+    /// Replaces the statement with `text`, keeping the trivia before its first token and
+    /// after its last one.
+    pub fn replace(&mut self, text: impl Into<Cow<'a, str>>) {
+        let mut token = Token::verbatim(text);
+        let mut tokens = std::mem::take(&mut self.children)
+            .into_iter()
+            .flat_map(|child| match child {
+                Element::Node(node) => node.into_tokens(),
+                Element::Token(token) => vec![token],
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(last) = tokens.last_mut() {
+            token.trailing = std::mem::take(&mut last.trailing);
+        }
+        if let Some(first) = tokens.first_mut() {
+            token.leading = std::mem::take(&mut first.leading);
+        }
+
+        self.children = vec![Element::Token(token)];
+    }
+
+    fn into_tokens(self) -> Vec<Token<'a>> {
+        self.children
+            .into_iter()
+            .flat_map(|child| match child {
+                Element::Node(node) => node.into_tokens(),
+                Element::Token(token) => vec![token],
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.children.iter().try_for_each(|child| child.fmt(f))
+    }
+}
+
+/// Concrete syntax tree of a whole file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTree<'a> {
+    /// [`NodeKind::File`] node holding the top-level statements.
+    pub root: Node<'a>,
+    /// Trivia after the last token.
+    pub end: Vec<Trivia<'a>>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// Builds the tree of `source`.
+    ///
+    /// This never fails: invalid input is kept in [`TokenKind::Error`] tokens and
+    /// [`NodeKind::Unknown`] nodes.
+    pub fn parse(source: &'a str) -> Self {
+        let (tokens, end) = tokenize(source);
+        let (ast, _) = parse_recovering(source);
+        let mut outliner = Outliner::default();
+        outliner.visit_root(&ast);
+
+        let mut builder = Builder {
+            tokens: tokens.into_iter(),
+        };
+
+        let children = builder.statements(&outliner.statements, usize::MAX);
+        let mut root = Node::new(NodeKind::File, children);
+        root.span = Span::new(0, source.len());
+
+        Self { root, end }
+    }
+
+    pub fn find(&self, span: Span) -> Option<&Node<'a>> {
+        self.root.find(span)
+    }
+
+    pub fn find_mut(&mut self, span: Span) -> Option<&mut Node<'a>> {
+        self.root.find_mut(span)
+    }
+}
+
+impl std::fmt::Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.root.fmt(f)?;
+        self.end
+            .iter()
+            .try_for_each(|trivia| f.write_str(&trivia.text))
+    }
+}
+
+/// Splits the source into tokens with trivia, and the trivia after the last token.
+fn tokenize(source: &str) -> (Vec<Token<'_>>, Vec<Trivia<'_>>) {
+    let mut tokens: Vec<Token> = vec![];
+    let mut trivia = vec![];
+    let mut offset = 0;
+
+    for token in lexer::Lexer::new(source) {
+        let (span, kind) = match token {
+            Ok((start, token, end)) => (Span::new(start, end), TokenKind::Token(token)),
+            Err(error) => (error.span(), TokenKind::Error(error.kind().clone())),
+        };
+
+        if offset < span.start {
+            trivia.push(Trivia::new(
+                TriviaKind::Whitespace,
+                &source[offset..span.start],
+            ));
+        }
+        offset = span.end;
+
+        let text = &source[span.range()];
+        let kind = match kind {
+            TokenKind::Token(lexer::Token::SingleLineComment(_)) => {
+                trivia.push(Trivia::new(TriviaKind::SingleLineComment, text));
+                continue;
+            }
+            TokenKind::Token(lexer::Token::MultiLineComment(_)) => {
+                trivia.push(Trivia::new(TriviaKind::MultiLineComment, text));
+                continue;
+            }
+            kind => kind,
+        };
+
+        let leading = match tokens.last_mut() {
+            Some(prev) => split_trailing(&mut trivia, &mut prev.trailing),
+            None => std::mem::take(&mut trivia),
+        };
+
+        tokens.push(Token {
+            kind,
+            text: Cow::from(text),
+            leading,
+            trailing: vec![],
+            span,
+        });
+    }
+
+    if offset < source.len() {
+        trivia.push(Trivia::new(TriviaKind::Whitespace, &source[offset..]));
+    }
+
+    let end = match tokens.last_mut() {
+        Some(prev) => split_trailing(&mut trivia, &mut prev.trailing),
+        None => trivia,
+    };
+
+    (tokens, end)
+}
+
+/// Moves the trivia up to the first line break into `trailing` and returns the rest.
+fn split_trailing<'a>(
+    trivia: &mut Vec<Trivia<'a>>,
+    trailing: &mut Vec<Trivia<'a>>,
+) -> Vec<Trivia<'a>> {
+    let mut rest = std::mem::take(trivia).into_iter();
+
+    for trivia in rest.by_ref() {
+        let Trivia {
+            kind: TriviaKind::Whitespace,
+            text: Cow::Borrowed(text),
+        } = trivia
+        else {
+            trailing.push(trivia);
+            continue;
+        };
+
+        let Some(index) = text.find('\n') else {
+            trailing.push(trivia);
+            continue;
+        };
+
+        if index > 0 {
+            trailing.push(Trivia::new(TriviaKind::Whitespace, &text[..index]));
+        }
+
+        let mut leading = vec![Trivia::new(TriviaKind::Whitespace, &text[index..])];
+        leading.extend(rest);
+        return leading;
+    }
+
+    vec![]
+}
+
+/// Statement reduced by the parser, with the statements of its body.
+struct Outline {
+    kind: NodeKind,
+    span: Span,
+    body: Vec<Outline>,
+}
+
+/// Collects the statements of an AST, nested as in the source.
+#[derive(Default)]
+struct Outliner {
+    statements: Vec<Outline>,
+}
+
+impl Outliner {
+    fn leaf(&mut self, kind: NodeKind, span: Span) {
+        self.block(kind, span, |_| {});
+    }
+
+    /// Adds a statement whose body is collected by `walk`.
+    fn block(&mut self, kind: NodeKind, span: Span, walk: impl FnOnce(&mut Self)) {
+        let outer = std::mem::take(&mut self.statements);
+        walk(self);
+
+        let body = std::mem::replace(&mut self.statements, outer);
+        self.statements.push(Outline { kind, span, body });
+    }
+}
+
+impl<'ast> Visit<'ast> for Outliner {
+    fn visit_syntax(&mut self, _scope: &Scope, syntax: &'ast ast::Syntax<'ast>) {
+        self.leaf(NodeKind::Syntax, syntax.span());
+    }
+
+    fn visit_package(&mut self, _scope: &Scope, package: &'ast ast::Package<'ast>) {
+        self.leaf(NodeKind::Package, package.span());
+    }
+
+    fn visit_import(&mut self, _scope: &Scope, import: &'ast ast::Import<'ast>) {
+        self.leaf(NodeKind::Import, import.span());
+    }
+
+    fn visit_option(&mut self, _scope: &Scope, option: &'ast ast::Option<'ast>) {
+        self.leaf(NodeKind::Option, option.span());
+    }
+
+    fn visit_service(&mut self, scope: &Scope, service: &'ast ast::Service<'ast>) {
+        self.block(NodeKind::Service, service.span(), |this| {
+            visit::walk_service(this, scope, service)
+        });
+    }
+
+    fn visit_rpc(&mut self, scope: &Scope, rpc: &'ast ast::Rpc<'ast>) {
+        self.block(NodeKind::Rpc, rpc.span(), |this| {
+            visit::walk_rpc(this, scope, rpc)
+        });
+    }
+
+    fn visit_message(&mut self, scope: &Scope, message: &'ast ast::Message<'ast>) {
+        self.block(NodeKind::Message, message.span(), |this| {
+            visit::walk_message(this, scope, message)
+        });
+    }
+
+    fn visit_field(&mut self, _scope: &Scope, field: &'ast ast::Field<'ast>) {
+        self.leaf(NodeKind::Field, field.span());
+    }
+
+    fn visit_group(&mut self, scope: &Scope, group: &'ast ast::Group<'ast>) {
+        // Options of the group sit in its header, so only the entries make up the body.
+        self.block(NodeKind::Group, group.span(), |this| {
+            for entry in &group.entries {
+                this.visit_message_entry(scope, entry);
+            }
+        });
+    }
+
+    fn visit_one_of(&mut self, scope: &Scope, one_of: &'ast ast::OneOf<'ast>) {
+        self.block(NodeKind::OneOf, one_of.span(), |this| {
+            visit::walk_one_of(this, scope, one_of)
+        });
+    }
+
+    fn visit_extend(&mut self, scope: &Scope, extend: &'ast ast::Extend<'ast>) {
+        self.block(NodeKind::Extend, extend.span(), |this| {
+            visit::walk_extend(this, scope, extend)
+        });
+    }
+
+    fn visit_enum(&mut self, scope: &Scope, r#enum: &'ast ast::Enum<'ast>) {
+        self.block(NodeKind::Enum, r#enum.span(), |this| {
+            visit::walk_enum(this, scope, r#enum)
+        });
+    }
+
+    fn visit_enum_variant(&mut self, _scope: &Scope, variant: &'ast ast::EnumVariant<'ast>) {
+        self.leaf(NodeKind::EnumVariant, variant.span());
+    }
+
+    fn visit_reserved_indices(&mut self, _scope: &Scope, indices: &'ast ast::ReservedIndices) {
+        self.leaf(NodeKind::Reserved, indices.span());
+    }
+
+    fn visit_reserved_idents(&mut self, _scope: &Scope, idents: &'ast ast::ReservedIdents<'ast>) {
+        self.leaf(NodeKind::Reserved, idents.span());
+    }
+
+    fn visit_extensions(&mut self, _scope: &Scope, extensions: &'ast ast::Extensions) {
+        self.leaf(NodeKind::Extensions, extensions.span());
+    }
+}
+
+/// Groups tokens into the statements the parser reduced.
+struct Builder<'a> {
+    tokens: std::vec::IntoIter<Token<'a>>,
+}
+
+impl<'a> Builder<'a> {
+    /// Collects the tokens before `end` into `statements`; tokens outside of them become
+    /// [`NodeKind::Empty`] and [`NodeKind::Unknown`] nodes. A `}` ending at `end` closes
+    /// the enclosing body and is left to its statement.
+    fn statements(&mut self, statements: &[Outline], end: usize) -> Vec<Element<'a>> {
+        let mut elements = vec![];
+        let mut statements = statements.iter().peekable();
+
+        while let Some(next) = self.tokens.as_slice().first() {
+            let start = next.span.start;
+            if start >= end || (is(next, &lexer::Token::CloseBrace) && next.span.end == end) {
+                break;
+            }
+
+            while statements
+                .next_if(|outline| outline.span.end <= start)
+                .is_some()
+            {}
+
+            let node = match statements.next_if(|outline| outline.span.start <= start) {
+                Some(outline) => self.statement(outline),
+                None => {
+                    let until = statements.peek().map_or(end, |outline| outline.span.start);
+                    self.stray(until.min(end))
+                }
+            };
+            elements.push(Element::Node(node));
+        }
+
+        elements
+    }
+
+    fn statement(&mut self, outline: &Outline) -> Node<'a> {
+        let mut children = vec![];
+        let mut depth = 0usize;
+
+        while let Some(token) = self.tokens.as_slice().first() {
+            if token.span.start >= outline.span.end {
+                break;
+            }
+
+            match token.token() {
+                // Brackets and parentheses only appear in headers, so the first `{` outside
+                // of them opens the body.
+                Some(lexer::Token::OpenBrace) if depth == 0 && outline.kind.has_body() => {
+                    children.push(Element::Token(self.tokens.next().unwrap()));
+                    children.extend(self.statements(&outline.body, outline.span.end));
+                    continue;
+                }
+                Some(lexer::Token::OpenBracket | lexer::Token::OpenPth) => depth += 1,
+                Some(lexer::Token::CloseBracket | lexer::Token::ClosePth) => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+
+            children.push(Element::Token(self.tokens.next().unwrap()));
+        }
+
+        node(outline.kind, children)
+    }
+
+    /// Builds a node of tokens no statement covers: a stray `;`, or everything up to the
+    /// next `;` or `until`.
+    fn stray(&mut self, until: usize) -> Node<'a> {
+        let first = self.tokens.next().unwrap();
+        if is(&first, &lexer::Token::Semicolon) {
+            return node(NodeKind::Empty, vec![Element::Token(first)]);
+        }
+
+        let mut children = vec![Element::Token(first)];
+
+        while let Some(token) = self.tokens.as_slice().first() {
+            if token.span.start >= until || is(token, &lexer::Token::CloseBrace) {
+                break;
+            }
+
+            let token = self.tokens.next().unwrap();
+            let semicolon = is(&token, &lexer::Token::Semicolon);
+            children.push(Element::Token(token));
+
+            if semicolon {
+                break;
+            }
+        }
+
+        node(NodeKind::Unknown, children)
+    }
+}
+
+fn node(kind: NodeKind, children: Vec<Element<'_>>) -> Node<'_> {
+    let mut node = Node::new(kind, children);
+    let start = node.tokens().next().map_or(0, |token| token.span.start);
+    let end = node.tokens().last().map_or(start, |token| token.span.end);
+
+    node.span = Span::new(start, end);
+    node
+}
+
+fn is(token: &Token, kind: &lexer::Token) -> bool {
+    token.token() == Some(kind)
+}
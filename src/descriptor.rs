@@ -0,0 +1,1242 @@
+//! Conversion of loaded files into `google.protobuf.FileDescriptorSet`, available with the
+//! `descriptor` feature.
+//!
+//! The output matches what `protoc --include_imports --include_source_info
+//! --descriptor_set_out` writes for the same files, with these differences:
+//!
+//! - Options other than the built-in ones of `descriptor.proto` (custom options and editions
+//!   `features`) are kept as `uninterpreted_option`, the form `protoc` uses before
+//!   interpreting them.
+//! - `SourceCodeInfo` has a location for every declaration and statement, but none for the
+//!   parts of a declaration (name, number, type, ...). Comments are only kept for the
+//!   declarations the AST attaches them to, not for statements such as `syntax` or `reserved`.
+//! - Editions files have `syntax = "editions"` but no `edition`, which the `prost-types`
+//!   descriptors do not have.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::descriptor;
+//! use protobuf_ast_parser::loader::Loader;
+//! use protobuf_ast_parser::source::Memory;
+//!
+//! let mut memory = Memory::new();
+//! memory.insert(
+//!     "user.proto",
+//!     "syntax = \"proto3\";\npackage app;\n\n// A user.\nmessage User { string name = 1; }",
+//! );
+//!
+//! let files = Loader::with_provider(memory).load(["user.proto"]).unwrap();
+//! let set = descriptor::file_descriptor_set(&files).unwrap();
+//! let user = &set.file[0].message_type[0];
+//! assert_eq!(user.name(), "User");
+//! assert_eq!(user.field[0].json_name(), "name");
+//!
+//! let location = &set.file[0].source_code_info.as_ref().unwrap().location[3];
+//! assert_eq!(location.path, [4, 0]);
+//! assert_eq!(location.leading_comments(), " A user.\n");
+//!
+//! let bytes = descriptor::encode(&files).unwrap();
+//! assert!(!bytes.is_empty());
+//! ```
+
+use crate::ast::{self, AttachedComments, Edition, Spanned};
+use crate::loader::{File, FileSet};
+use crate::resolve::{ResolveError, ResolveErrorKind, SymbolKind, SymbolTable};
+use crate::source::canonical_name;
+use crate::span::{Position, Span};
+use crate::visit::{self, Scope};
+use prost::Message as _;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::source_code_info::Location;
+use prost_types::uninterpreted_option::NamePart;
+use prost_types::{
+    descriptor_proto, enum_descriptor_proto, field_options, file_options, method_options,
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto, EnumValueOptions,
+    FieldDescriptorProto, FieldOptions, FileDescriptorProto, FileDescriptorSet, FileOptions,
+    MessageOptions, MethodDescriptorProto, MethodOptions, OneofDescriptorProto, OneofOptions,
+    ServiceDescriptorProto, ServiceOptions, SourceCodeInfo, UninterpretedOption,
+};
+use std::collections::HashSet;
+
+pub use prost_types;
+
+/// Largest field number, the value of `max` in messages.
+const FIELD_NUMBER_MAX: i32 = 536_870_911;
+
+/// Converts every file of `files`, imports first, into a `FileDescriptorSet`.
+///
+/// Type references are resolved as by [`resolve`](crate::resolve::resolve), whether or not
+/// `files` were resolved already.
+pub fn file_descriptor_set(files: &FileSet) -> Result<FileDescriptorSet, Vec<DescriptorError>> {
+    let symbols = SymbolTable::build(files).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(DescriptorError::from)
+            .collect::<Vec<_>>()
+    })?;
+    let mut set = FileDescriptorSet::default();
+    let mut errors = Vec::new();
+
+    for file in files {
+        match file_descriptor(file, &symbols) {
+            Ok(file) => set.file.push(file),
+            Err(file_errors) => errors.extend(file_errors),
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(set),
+        false => Err(errors),
+    }
+}
+
+/// Converts `files` like [`file_descriptor_set`] and encodes the result as protobuf binary.
+pub fn encode(files: &FileSet) -> Result<Vec<u8>, Vec<DescriptorError>> {
+    Ok(file_descriptor_set(files)?.encode_to_vec())
+}
+
+/// Converts a single file, resolving its type references against `symbols`.
+pub fn file_descriptor(
+    file: &File,
+    symbols: &SymbolTable,
+) -> Result<FileDescriptorProto, Vec<DescriptorError>> {
+    let mut builder = Builder {
+        file,
+        symbols,
+        lines: LineIndex::new(&file.source),
+        edition: Edition::default(),
+        locations: Vec::new(),
+        errors: Vec::new(),
+        extend: false,
+    };
+    let proto = builder.file();
+
+    match builder.errors.is_empty() {
+        true => Ok(proto),
+        false => Err(builder.errors),
+    }
+}
+
+/// Error raised while converting files into descriptors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DescriptorError {
+    /// A declaration or type reference does not resolve.
+    Resolve(ResolveError),
+    /// `number` lies outside `min..=max`, the numbers allowed here; `file` is the import path
+    /// of the file containing the offending declaration.
+    OutOfRange {
+        number: i128,
+        min: i32,
+        max: i32,
+        file: String,
+        span: Span,
+        position: Position,
+    },
+}
+
+impl From<ResolveError> for DescriptorError {
+    fn from(error: ResolveError) -> Self {
+        DescriptorError::Resolve(error)
+    }
+}
+
+impl std::fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DescriptorError::Resolve(error) => write!(f, "{}", error),
+            DescriptorError::OutOfRange {
+                number,
+                min,
+                max,
+                file,
+                position,
+                ..
+            } => write!(
+                f,
+                "{} is out of range, expected {} to {} at {} {}",
+                number, min, max, file, position
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DescriptorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DescriptorError::Resolve(error) => Some(error),
+            DescriptorError::OutOfRange { .. } => None,
+        }
+    }
+}
+
+struct Builder<'b> {
+    file: &'b File,
+    symbols: &'b SymbolTable,
+    lines: LineIndex<'b>,
+    edition: Edition,
+    locations: Vec<Location>,
+    errors: Vec<DescriptorError>,
+    /// Whether fields being converted are declared directly in an `extend` block.
+    extend: bool,
+}
+
+impl<'b> Builder<'b> {
+    fn file(&mut self) -> FileDescriptorProto {
+        let file = self.file;
+        let scope = visit::root_scope(&file.ast);
+        let mut proto = FileDescriptorProto {
+            name: Some(file.name.clone()),
+            ..Default::default()
+        };
+        let mut options = Vec::new();
+
+        let mut spans = file.ast.iter().filter_map(|entry| match entry {
+            ast::RootEntry::Comment(_) => None,
+            entry => Some(entry.span()),
+        });
+        if let Some(first) = spans.next() {
+            let last = spans.next_back().unwrap_or(first);
+            self.locate(vec![], Span::new(first.start, last.end), None);
+        }
+
+        for entry in &file.ast {
+            match entry {
+                ast::RootEntry::Comment(_) => {}
+                ast::RootEntry::Syntax(syntax) => {
+                    self.edition = syntax.version().unwrap_or_default();
+                    proto.syntax = match self.edition {
+                        Edition::Proto2 => None,
+                        Edition::Proto3 => Some("proto3".to_string()),
+                        _ => Some("editions".to_string()),
+                    };
+                    self.locate(vec![12], syntax.span, None);
+                }
+                ast::RootEntry::Package(package) => {
+                    proto.package = Some(package.path.to_string());
+                    self.locate(vec![2], package.span, None);
+                }
+                ast::RootEntry::Import(import) => {
+                    let index = proto.dependency.len() as i32;
+                    proto
+                        .dependency
+                        .push(canonical_name(&String::from_utf8_lossy(
+                            import.path.as_bytes(),
+                        )));
+
+                    match import.kind {
+                        ast::ImportKind::Default => {}
+                        ast::ImportKind::Public => proto.public_dependency.push(index),
+                        ast::ImportKind::Weak => proto.weak_dependency.push(index),
+                    }
+                    self.locate(vec![3, index], import.span, None);
+                }
+                ast::RootEntry::Option(option) => {
+                    options.push(option);
+                    self.locate(vec![8], option.span, None);
+                }
+                ast::RootEntry::Service(service) => {
+                    let path = vec![6, proto.service.len() as i32];
+                    proto.service.push(self.service(&scope, service, path));
+                }
+                ast::RootEntry::Message(message) => {
+                    let path = vec![4, proto.message_type.len() as i32];
+                    let message = self.message(
+                        &scope,
+                        &message.ident,
+                        &message.entries,
+                        message.span,
+                        Some(&message.comments),
+                        path,
+                    );
+                    proto.message_type.push(message);
+                }
+                ast::RootEntry::Extend(extend) => self.extend(
+                    &scope,
+                    extend,
+                    (&mut proto.extension, vec![7]),
+                    (&mut proto.message_type, vec![4]),
+                ),
+                ast::RootEntry::Enum(r#enum) => {
+                    let path = vec![5, proto.enum_type.len() as i32];
+                    proto.enum_type.push(self.r#enum(r#enum, path));
+                }
+            }
+        }
+
+        proto.options = convert_options(options);
+        proto.source_code_info = Some(SourceCodeInfo {
+            location: std::mem::take(&mut self.locations),
+        });
+        proto
+    }
+
+    /// Converts a message, or the message type of a group.
+    fn message(
+        &mut self,
+        scope: &Scope,
+        name: &str,
+        entries: &[ast::MessageEntry],
+        span: Span,
+        comments: Option<&AttachedComments>,
+        path: Vec<i32>,
+    ) -> DescriptorProto {
+        self.locate(path.clone(), span, comments);
+
+        let extend = std::mem::replace(&mut self.extend, false);
+        let scope = scope.child(name);
+        let mut proto = DescriptorProto {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+        let mut options = Vec::new();
+        let mut reserved = Vec::new();
+        let mut extensions = Vec::new();
+
+        for entry in entries {
+            match entry {
+                ast::MessageEntry::Comment(_) => {}
+                ast::MessageEntry::Option(option) => {
+                    options.push(option);
+                    self.locate(child(&path, &[7]), option.span, None);
+                }
+                ast::MessageEntry::Field(field) => {
+                    let field_path = child(&path, &[2, proto.field.len() as i32]);
+                    let field = self.field(
+                        &scope,
+                        field,
+                        None,
+                        field_path,
+                        (&mut proto.nested_type, child(&path, &[3])),
+                    );
+                    proto.field.push(field);
+                }
+                ast::MessageEntry::Group(group) => {
+                    let field_path = child(&path, &[2, proto.field.len() as i32]);
+                    let field = self.group(
+                        &scope,
+                        group,
+                        None,
+                        field_path,
+                        (&mut proto.nested_type, child(&path, &[3])),
+                    );
+                    proto.field.push(field);
+                }
+                ast::MessageEntry::OneOf(one_of) => {
+                    let index = proto.oneof_decl.len() as i32;
+                    let one_of_path = child(&path, &[8, index]);
+                    let mut one_of_options = Vec::new();
+                    self.locate(one_of_path.clone(), one_of.span, Some(&one_of.comments));
+
+                    for entry in &one_of.entries {
+                        let field_path = child(&path, &[2, proto.field.len() as i32]);
+                        let types = (&mut proto.nested_type, child(&path, &[3]));
+
+                        match entry {
+                            ast::OneOfEntry::Comment(_) => {}
+                            ast::OneOfEntry::Option(option) => {
+                                one_of_options.push(option);
+                                self.locate(child(&one_of_path, &[2]), option.span, None);
+                            }
+                            ast::OneOfEntry::Field(field) => {
+                                let field =
+                                    self.field(&scope, field, Some(index), field_path, types);
+                                proto.field.push(field);
+                            }
+                            ast::OneOfEntry::Group(group) => {
+                                let field =
+                                    self.group(&scope, group, Some(index), field_path, types);
+                                proto.field.push(field);
+                            }
+                        }
+                    }
+
+                    proto.oneof_decl.push(OneofDescriptorProto {
+                        name: Some(one_of.ident.to_string()),
+                        options: convert_options(one_of_options),
+                    });
+                }
+                ast::MessageEntry::Message(message) => {
+                    let message_path = child(&path, &[3, proto.nested_type.len() as i32]);
+                    let message = self.message(
+                        &scope,
+                        &message.ident,
+                        &message.entries,
+                        message.span,
+                        Some(&message.comments),
+                        message_path,
+                    );
+                    proto.nested_type.push(message);
+                }
+                ast::MessageEntry::Extend(extend) => self.extend(
+                    &scope,
+                    extend,
+                    (&mut proto.extension, child(&path, &[6])),
+                    (&mut proto.nested_type, child(&path, &[3])),
+                ),
+                ast::MessageEntry::Enum(r#enum) => {
+                    let enum_path = child(&path, &[4, proto.enum_type.len() as i32]);
+                    proto.enum_type.push(self.r#enum(r#enum, enum_path));
+                }
+                ast::MessageEntry::ReservedIndices(indices) => {
+                    reserved.extend(indices.iter().map(|range| (range, indices.span)));
+                    self.locate(child(&path, &[9]), indices.span, None);
+                }
+                ast::MessageEntry::ReservedIdents(idents) => {
+                    proto.reserved_name.extend(idents.iter().map(string));
+                    self.locate(child(&path, &[10]), idents.span, None);
+                }
+                ast::MessageEntry::Extensions(ranges) => {
+                    extensions.extend(ranges.iter().map(|range| (range, ranges.span)));
+                    self.locate(child(&path, &[5]), ranges.span, None);
+                }
+            }
+        }
+
+        proto.options = convert_options::<MessageOptions>(options);
+
+        // Message sets allow any 32-bit tag, and message ranges exclude their end.
+        let max = match proto.options.as_ref() {
+            Some(options) if options.message_set_wire_format() => i32::MAX - 1,
+            _ => FIELD_NUMBER_MAX,
+        };
+        proto.reserved_range = reserved
+            .into_iter()
+            .filter_map(|(range, span)| {
+                let (start, end) = self.range(range, (1, max), span)?;
+                Some(descriptor_proto::ReservedRange {
+                    start: Some(start),
+                    end: Some(end + 1),
+                })
+            })
+            .collect();
+        proto.extension_range = extensions
+            .into_iter()
+            .filter_map(|(range, span)| {
+                let (start, end) = self.range(range, (1, max), span)?;
+                Some(descriptor_proto::ExtensionRange {
+                    start: Some(start),
+                    end: Some(end + 1),
+                    options: None,
+                })
+            })
+            .collect();
+
+        if self.edition == Edition::Proto3 {
+            synthesize_oneofs(&mut proto);
+        }
+
+        self.extend = extend;
+        proto
+    }
+
+    fn field(
+        &mut self,
+        scope: &Scope,
+        field: &ast::Field,
+        one_of: Option<i32>,
+        path: Vec<i32>,
+        (types, _): (&mut Vec<DescriptorProto>, Vec<i32>),
+    ) -> FieldDescriptorProto {
+        self.locate(path, field.span, Some(&field.comments));
+
+        let mut proto = FieldDescriptorProto {
+            name: Some(field.ident.to_string()),
+            number: self.field_number(field.index, field.span),
+            label: Some(label(field.modifier.as_ref()) as i32),
+            json_name: Some(json_name(&field.ident)),
+            oneof_index: one_of,
+            ..Default::default()
+        };
+
+        if self.edition == Edition::Proto3
+            && one_of.is_none()
+            && field.modifier == Some(ast::FieldModifier::Optional)
+        {
+            proto.proto3_optional = Some(true);
+        }
+
+        match &field.r#type {
+            ast::FieldType::Map { key, value } => {
+                let entry = self.map_entry(scope, &field.ident, key, value, field.span);
+
+                proto.label = Some(Label::Repeated as i32);
+                proto.r#type = Some(Type::Message as i32);
+                proto.type_name = Some(format!(".{}", scope.qualify(entry.name())));
+                types.push(entry);
+            }
+            r#type => {
+                if let Some((r#type, name)) = self.field_type(scope, r#type, field.span) {
+                    proto.r#type = Some(r#type as i32);
+                    proto.type_name = name;
+                }
+            }
+        }
+
+        let mut options = Vec::new();
+
+        for option in &field.options {
+            match option.name.to_string().as_str() {
+                "default" => proto.default_value = default_value(proto.r#type(), &option.value),
+                "json_name" => {
+                    if let ast::MapValue::String(literal) = &option.value {
+                        proto.json_name = Some(string(literal));
+                    }
+                }
+                _ => options.push(option),
+            }
+        }
+
+        proto.options = convert_options(options);
+        proto
+    }
+
+    /// Converts a group into its field, adding its message type to `types`.
+    fn group(
+        &mut self,
+        scope: &Scope,
+        group: &ast::Group,
+        one_of: Option<i32>,
+        path: Vec<i32>,
+        (types, types_path): (&mut Vec<DescriptorProto>, Vec<i32>),
+    ) -> FieldDescriptorProto {
+        self.locate(path, group.span, Some(&group.comments));
+
+        let name = group.ident.to_ascii_lowercase();
+        let proto = FieldDescriptorProto {
+            json_name: Some(json_name(&name)),
+            name: Some(name),
+            number: self.field_number(group.index, group.span),
+            label: Some(label(group.modifier.as_ref()) as i32),
+            r#type: Some(Type::Group as i32),
+            type_name: Some(format!(".{}", scope.qualify(&group.ident))),
+            oneof_index: one_of,
+            options: convert_options(&group.options),
+            ..Default::default()
+        };
+
+        let message_path = child(&types_path, &[types.len() as i32]);
+        let message = self.message(
+            scope,
+            &group.ident,
+            &group.entries,
+            group.span,
+            None,
+            message_path,
+        );
+        types.push(message);
+
+        proto
+    }
+
+    /// Builds the `XxxEntry` message type `protoc` generates for a `map` field.
+    fn map_entry(
+        &mut self,
+        scope: &Scope,
+        field: &str,
+        key: &ast::FieldType,
+        value: &ast::FieldType,
+        span: Span,
+    ) -> DescriptorProto {
+        let mut fields = Vec::new();
+
+        for (number, (name, r#type)) in [("key", key), ("value", value)].into_iter().enumerate() {
+            let mut proto = FieldDescriptorProto {
+                name: Some(name.to_string()),
+                number: Some(number as i32 + 1),
+                label: Some(Label::Optional as i32),
+                json_name: Some(name.to_string()),
+                ..Default::default()
+            };
+
+            if let Some((r#type, name)) = self.field_type(scope, r#type, span) {
+                proto.r#type = Some(r#type as i32);
+                proto.type_name = name;
+            }
+            fields.push(proto);
+        }
+
+        DescriptorProto {
+            name: Some(format!("{}Entry", camel_case(field))),
+            field: fields,
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the type of a non-map field and, for messages and enums, its full name.
+    fn field_type(
+        &mut self,
+        scope: &Scope,
+        r#type: &ast::FieldType,
+        span: Span,
+    ) -> Option<(Type, Option<String>)> {
+        let name = match r#type {
+            ast::FieldType::Scalar(scalar) => return Some((scalar_type(*scalar), None)),
+            ast::FieldType::Absolute(name) => format!(".{}", name),
+            ast::FieldType::Relative(name) => name.to_string(),
+            ast::FieldType::Map { .. } => return None,
+        };
+
+        let resolved = self.resolve(
+            self.symbols.lookup_type(&self.file.name, scope, &name),
+            span,
+        )?;
+        let r#type = match self.symbols.get(&resolved).map(|symbol| symbol.kind) {
+            Some(SymbolKind::Enum) => Type::Enum,
+            _ => Type::Message,
+        };
+
+        Some((r#type, Some(format!(".{}", resolved))))
+    }
+
+    fn extend(
+        &mut self,
+        scope: &Scope,
+        extend: &ast::Extend,
+        (fields, fields_path): (&mut Vec<FieldDescriptorProto>, Vec<i32>),
+        (types, types_path): (&mut Vec<DescriptorProto>, Vec<i32>),
+    ) {
+        self.locate(fields_path.clone(), extend.span, None);
+
+        let extendee = self
+            .resolve(
+                self.symbols
+                    .lookup_message(&self.file.name, scope, &extend.r#type),
+                extend.span,
+            )
+            .map(|resolved| format!(".{}", resolved));
+
+        let outer = std::mem::replace(&mut self.extend, true);
+
+        for entry in &extend.entries {
+            let path = child(&fields_path, &[fields.len() as i32]);
+            let types = (&mut *types, types_path.clone());
+
+            let mut field = match entry {
+                ast::ExtendEntry::Comment(_) => continue,
+                ast::ExtendEntry::Field(field) => self.field(scope, field, None, path, types),
+                ast::ExtendEntry::Group(group) => self.group(scope, group, None, path, types),
+            };
+
+            field.extendee = extendee.clone();
+            fields.push(field);
+        }
+
+        self.extend = outer;
+    }
+
+    fn r#enum(&mut self, r#enum: &ast::Enum, path: Vec<i32>) -> EnumDescriptorProto {
+        self.locate(path.clone(), r#enum.span, Some(&r#enum.comments));
+
+        let mut proto = EnumDescriptorProto {
+            name: Some(r#enum.ident.to_string()),
+            ..Default::default()
+        };
+        let mut options = Vec::new();
+
+        for entry in &r#enum.entries {
+            match entry {
+                ast::EnumEntry::Comment(_) => {}
+                ast::EnumEntry::Option(option) => {
+                    options.push(option);
+                    self.locate(child(&path, &[3]), option.span, None);
+                }
+                ast::EnumEntry::Variant(variant) => {
+                    let variant_path = child(&path, &[2, proto.value.len() as i32]);
+                    self.locate(variant_path, variant.span, Some(&variant.comments));
+
+                    proto.value.push(EnumValueDescriptorProto {
+                        name: Some(variant.ident.to_string()),
+                        number: self.number(variant.value, (i32::MIN, i32::MAX), variant.span),
+                        options: convert_options(&variant.options),
+                    });
+                }
+                ast::EnumEntry::ReservedIndices(indices) => {
+                    for range in indices.iter() {
+                        let bounds = (i32::MIN, i32::MAX);
+                        if let Some((start, end)) = self.range(range, bounds, indices.span) {
+                            proto
+                                .reserved_range
+                                .push(enum_descriptor_proto::EnumReservedRange {
+                                    start: Some(start),
+                                    end: Some(end),
+                                });
+                        }
+                    }
+                    self.locate(child(&path, &[4]), indices.span, None);
+                }
+                ast::EnumEntry::ReservedIdents(idents) => {
+                    proto.reserved_name.extend(idents.iter().map(string));
+                    self.locate(child(&path, &[5]), idents.span, None);
+                }
+            }
+        }
+
+        proto.options = convert_options(options);
+        proto
+    }
+
+    fn service(
+        &mut self,
+        scope: &Scope,
+        service: &ast::Service,
+        path: Vec<i32>,
+    ) -> ServiceDescriptorProto {
+        self.locate(path.clone(), service.span, Some(&service.comments));
+
+        let scope = scope.child(&service.ident);
+        let mut proto = ServiceDescriptorProto {
+            name: Some(service.ident.to_string()),
+            ..Default::default()
+        };
+        let mut options = Vec::new();
+
+        for entry in &service.entries {
+            match entry {
+                ast::ServiceEntry::Comment(_) => {}
+                ast::ServiceEntry::Option(option) => {
+                    options.push(option);
+                    self.locate(child(&path, &[3]), option.span, None);
+                }
+                ast::ServiceEntry::Rpc(rpc) => {
+                    let method_path = child(&path, &[2, proto.method.len() as i32]);
+                    proto.method.push(self.method(&scope, rpc, method_path));
+                }
+            }
+        }
+
+        proto.options = convert_options(options);
+        proto
+    }
+
+    fn method(&mut self, scope: &Scope, rpc: &ast::Rpc, path: Vec<i32>) -> MethodDescriptorProto {
+        self.locate(path.clone(), rpc.span, Some(&rpc.comments));
+
+        let mut message = |name: &str| {
+            self.resolve(
+                self.symbols.lookup_message(&self.file.name, scope, name),
+                rpc.span,
+            )
+            .map(|resolved| format!(".{}", resolved))
+        };
+        let input_type = message(&rpc.request);
+        let output_type = message(&rpc.reply);
+
+        let mut options = Vec::new();
+
+        for entry in &rpc.entries {
+            if let ast::RpcEntry::Option(option) = entry {
+                options.push(option);
+                self.locate(child(&path, &[4]), option.span, None);
+            }
+        }
+
+        MethodDescriptorProto {
+            name: Some(rpc.ident.to_string()),
+            input_type,
+            output_type,
+            options: convert_options(options),
+            client_streaming: matches!(
+                rpc.stream,
+                ast::RpcStream::ServerBound | ast::RpcStream::Bidirectional
+            )
+            .then_some(true),
+            server_streaming: matches!(
+                rpc.stream,
+                ast::RpcStream::ClientBound | ast::RpcStream::Bidirectional
+            )
+            .then_some(true),
+        }
+    }
+
+    fn resolve(&mut self, result: Result<String, ResolveErrorKind>, span: Span) -> Option<String> {
+        match result {
+            Ok(resolved) => Some(resolved),
+            Err(kind) => {
+                let file = self.file;
+                self.errors
+                    .push(ResolveError::new(kind, &file.name, &file.source, span).into());
+                None
+            }
+        }
+    }
+
+    /// Converts a field number; extensions may use any positive 32-bit number, since their
+    /// extendee can be a message set.
+    fn field_number(&mut self, number: i128, span: Span) -> Option<i32> {
+        let max = match self.extend {
+            true => i32::MAX,
+            false => FIELD_NUMBER_MAX,
+        };
+
+        self.number(number, (1, max), span)
+    }
+
+    /// First and last number of a range; finite ranges are stored half-open.
+    fn range(&mut self, range: &ast::Range, bounds: (i32, i32), span: Span) -> Option<(i32, i32)> {
+        let (start, end) = match range {
+            ast::Range::Default(range) => (range.start, range.end - 1),
+            ast::Range::From(range) => (range.start, bounds.1 as i128),
+        };
+
+        Some((
+            self.number(start, bounds, span)?,
+            self.number(end, bounds, span)?,
+        ))
+    }
+
+    /// Converts `number` to `i32`, reporting it when it lies outside `min..=max`.
+    fn number(&mut self, number: i128, (min, max): (i32, i32), span: Span) -> Option<i32> {
+        match i32::try_from(number) {
+            Ok(number) if (min..=max).contains(&number) => Some(number),
+            _ => {
+                self.errors.push(DescriptorError::OutOfRange {
+                    number,
+                    min,
+                    max,
+                    file: self.file.name.clone(),
+                    span,
+                    position: span.start_position(&self.file.source),
+                });
+                None
+            }
+        }
+    }
+
+    fn locate(&mut self, path: Vec<i32>, span: Span, comments: Option<&AttachedComments>) {
+        let comments = comments.cloned().unwrap_or_default();
+
+        self.locations.push(Location {
+            path,
+            span: self.lines.span(span),
+            leading_comments: comments.leading,
+            trailing_comments: comments.trailing,
+            leading_detached_comments: comments.leading_detached,
+        });
+    }
+}
+
+/// Line starts of a source, to turn byte offsets into `protoc` line/column pairs.
+struct LineIndex<'s> {
+    source: &'s str,
+    starts: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    fn new(source: &'s str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self { source, starts }
+    }
+
+    /// Zero-based line and column of `offset`; like `protoc`, tabs advance to the next
+    /// multiple of 8.
+    fn position(&self, offset: usize) -> (i32, i32) {
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let column =
+            self.source[self.starts[line]..offset]
+                .chars()
+                .fold(0, |column, ch| match ch {
+                    '\t' => column + 8 - column % 8,
+                    _ => column + 1,
+                });
+
+        (line as i32, column)
+    }
+
+    /// `SourceCodeInfo` span: start line, start column, end line (omitted when it is the
+    /// start line) and end column.
+    fn span(&self, span: Span) -> Vec<i32> {
+        let (start_line, start_column) = self.position(span.start);
+        let (end_line, end_column) = self.position(span.end);
+
+        match start_line == end_line {
+            true => vec![start_line, start_column, end_column],
+            false => vec![start_line, start_column, end_line, end_column],
+        }
+    }
+}
+
+fn child(path: &[i32], suffix: &[i32]) -> Vec<i32> {
+    [path, suffix].concat()
+}
+
+fn string(literal: &ast::StringLiteral) -> String {
+    String::from_utf8_lossy(literal.as_bytes()).into_owned()
+}
+
+fn label(modifier: Option<&ast::FieldModifier>) -> Label {
+    match modifier {
+        Some(ast::FieldModifier::Required) => Label::Required,
+        Some(ast::FieldModifier::Repeated) => Label::Repeated,
+        Some(ast::FieldModifier::Optional) | None => Label::Optional,
+    }
+}
+
+fn scalar_type(scalar: ast::ScalarType) -> Type {
+    match scalar {
+        ast::ScalarType::Double => Type::Double,
+        ast::ScalarType::Float => Type::Float,
+        ast::ScalarType::Int32 => Type::Int32,
+        ast::ScalarType::Int64 => Type::Int64,
+        ast::ScalarType::Uint32 => Type::Uint32,
+        ast::ScalarType::Uint64 => Type::Uint64,
+        ast::ScalarType::Sint32 => Type::Sint32,
+        ast::ScalarType::Sint64 => Type::Sint64,
+        ast::ScalarType::Fixed32 => Type::Fixed32,
+        ast::ScalarType::Fixed64 => Type::Fixed64,
+        ast::ScalarType::Sfixed32 => Type::Sfixed32,
+        ast::ScalarType::Sfixed64 => Type::Sfixed64,
+        ast::ScalarType::Bool => Type::Bool,
+        ast::ScalarType::String => Type::String,
+        ast::ScalarType::Bytes => Type::Bytes,
+    }
+}
+
+/// JSON name of a field: underscores are dropped and the letter after each is capitalized.
+fn json_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut capitalize = false;
+
+    for ch in name.chars() {
+        match ch {
+            '_' => capitalize = true,
+            ch if capitalize => {
+                result.push(ch.to_ascii_uppercase());
+                capitalize = false;
+            }
+            ch => result.push(ch),
+        }
+    }
+
+    result
+}
+
+/// Like [`json_name`], but the first letter is capitalized too.
+fn camel_case(name: &str) -> String {
+    let name = json_name(name);
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => name,
+    }
+}
+
+/// Adds the oneofs `protoc` synthesizes for proto3 `optional` fields, after the real ones.
+fn synthesize_oneofs(proto: &mut DescriptorProto) {
+    let mut names: HashSet<String> = proto
+        .field
+        .iter()
+        .map(|field| field.name().to_string())
+        .chain(
+            proto
+                .oneof_decl
+                .iter()
+                .map(|one_of| one_of.name().to_string()),
+        )
+        .collect();
+
+    for field in proto
+        .field
+        .iter_mut()
+        .filter(|field| field.proto3_optional())
+    {
+        let mut name = match field.name().starts_with('_') {
+            true => field.name().to_string(),
+            false => format!("_{}", field.name()),
+        };
+        while names.contains(&name) {
+            name.insert(0, 'X');
+        }
+
+        names.insert(name.clone());
+        field.oneof_index = Some(proto.oneof_decl.len() as i32);
+        proto.oneof_decl.push(OneofDescriptorProto {
+            name: Some(name),
+            options: None,
+        });
+    }
+}
+
+/// Text of a `[default = ...]` value as `FieldDescriptorProto::default_value` stores it.
+fn default_value(r#type: Type, value: &ast::MapValue) -> Option<String> {
+    let text = match value {
+        ast::MapValue::Boolean(value) => value.to_string(),
+        ast::MapValue::Integer(value) if matches!(r#type, Type::Float | Type::Double) => {
+            float(r#type, *value as f64)
+        }
+        ast::MapValue::Integer(value) => value.to_string(),
+        ast::MapValue::Float(value) if value.is_nan() => "nan".to_string(),
+        ast::MapValue::Float(value) if value.is_infinite() => match value.is_sign_positive() {
+            true => "inf".to_string(),
+            false => "-inf".to_string(),
+        },
+        ast::MapValue::Float(value) => float(r#type, *value),
+        ast::MapValue::Ident(ident) => ident.to_string(),
+        ast::MapValue::String(literal) if r#type == Type::Bytes => c_escape(literal.as_bytes()),
+        ast::MapValue::String(literal) => string(literal),
+        ast::MapValue::Map(_) | ast::MapValue::List(_) => return None,
+    };
+
+    Some(text)
+}
+
+/// Formats a finite float default the way `protoc` does: `%g` with 15 significant digits, or 17
+/// if that does not read back as the same value. `float` fields use 6 and 9 digits of the value
+/// rounded to `f32`.
+fn float(r#type: Type, value: f64) -> String {
+    if r#type == Type::Float {
+        let value = value as f32;
+        let text = general(value as f64, 6);
+
+        return match text.parse() == Ok(value) {
+            true => text,
+            false => general(value as f64, 9),
+        };
+    }
+
+    let text = general(value, 15);
+
+    match text.parse() == Ok(value) {
+        true => text,
+        false => general(value, 17),
+    }
+}
+
+/// C's `%.{precision}g`: fixed or exponent notation depending on the exponent, without
+/// trailing zeros.
+fn general(value: f64, precision: usize) -> String {
+    if value == 0.0 {
+        return match value.is_sign_negative() {
+            true => "-0".to_string(),
+            false => "0".to_string(),
+        };
+    }
+
+    let scientific = format!("{:.*e}", precision - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    if exponent < -4 || exponent >= precision as i32 {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        return format!("{}e{}{:02}", trim_zeros(mantissa), sign, exponent.abs());
+    }
+
+    let decimals = (precision as i32 - 1 - exponent) as usize;
+    trim_zeros(&format!("{:.*}", decimals, value)).to_string()
+}
+
+/// Strips trailing zeros after a decimal point, and the point itself if nothing follows it.
+fn trim_zeros(text: &str) -> &str {
+    match text.contains('.') {
+        true => text.trim_end_matches('0').trim_end_matches('.'),
+        false => text,
+    }
+}
+
+/// Escapes bytes the way `protoc` writes `bytes` defaults.
+fn c_escape(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            b'\n' => "\\n".to_string(),
+            b'\r' => "\\r".to_string(),
+            b'\t' => "\\t".to_string(),
+            b'"' => "\\\"".to_string(),
+            b'\'' => "\\'".to_string(),
+            b'\\' => "\\\\".to_string(),
+            0x20..=0x7e => (byte as char).to_string(),
+            _ => format!("\\{:03o}", byte),
+        })
+        .collect()
+}
+
+/// Options message of a descriptor, filled from `option` statements.
+trait Options: Default {
+    /// Sets a built-in option; returns `false` if `name` is unknown or `value` does not fit.
+    fn set(&mut self, name: &str, value: &ast::MapValue) -> bool;
+
+    fn uninterpreted(&mut self) -> &mut Vec<UninterpretedOption>;
+}
+
+fn convert_options<'o, O: Options>(
+    options: impl IntoIterator<Item = &'o ast::Option<'o>>,
+) -> Option<O> {
+    let mut result = None;
+
+    for option in options {
+        let converted: &mut O = result.get_or_insert_with(O::default);
+        let name = match option.name.as_slice() {
+            [ast::OptionNameSegment::Plain(name)] => Some(name),
+            _ => None,
+        };
+
+        if !name.is_some_and(|name| converted.set(name, &option.value)) {
+            converted.uninterpreted().push(uninterpreted(option));
+        }
+    }
+
+    result
+}
+
+fn uninterpreted(option: &ast::Option) -> UninterpretedOption {
+    let mut proto = UninterpretedOption {
+        name: option
+            .name
+            .iter()
+            .map(|segment| match segment {
+                ast::OptionNameSegment::Plain(name) => NamePart {
+                    name_part: name.to_string(),
+                    is_extension: false,
+                },
+                ast::OptionNameSegment::Extension(name) => NamePart {
+                    name_part: name.to_string(),
+                    is_extension: true,
+                },
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    match &option.value {
+        ast::MapValue::Boolean(value) => proto.identifier_value = Some(value.to_string()),
+        ast::MapValue::Integer(value) if *value >= 0 => {
+            proto.positive_int_value = u64::try_from(*value).ok()
+        }
+        ast::MapValue::Integer(value) => proto.negative_int_value = i64::try_from(*value).ok(),
+        ast::MapValue::Float(value) => proto.double_value = Some(*value),
+        ast::MapValue::Ident(ident) => proto.identifier_value = Some(ident.to_string()),
+        ast::MapValue::String(literal) => proto.string_value = Some(literal.as_bytes().to_vec()),
+        ast::MapValue::Map(map) => proto.aggregate_value = Some(aggregate(map)),
+        ast::MapValue::List(_) => proto.aggregate_value = Some(option.value.to_string()),
+    }
+
+    proto
+}
+
+/// Text format of a message literal without its braces, on a single line.
+fn aggregate(map: &ast::Map) -> String {
+    map.iter()
+        .map(|(key, value)| match value {
+            ast::MapValue::Map(map) => format!("{} {{ {} }}", key, aggregate(map)),
+            value => format!("{}: {}", key, value),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn boolean(value: &ast::MapValue) -> Option<bool> {
+    match value {
+        ast::MapValue::Boolean(value) => Some(*value),
+        ast::MapValue::Ident(ident) => ident.parse().ok(),
+        _ => None,
+    }
+}
+
+fn text(value: &ast::MapValue) -> Option<String> {
+    match value {
+        ast::MapValue::String(literal) => literal.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+fn enumeration<E: Into<i32>>(value: &ast::MapValue, parse: fn(&str) -> Option<E>) -> Option<i32> {
+    match value {
+        ast::MapValue::Ident(ident) => parse(ident).map(Into::into),
+        _ => None,
+    }
+}
+
+macro_rules! options {
+    ($($type:ty { $($field:ident: $convert:expr),* $(,)? })*) => {$(
+        impl Options for $type {
+            #[allow(deprecated)]
+            fn set(&mut self, name: &str, value: &ast::MapValue) -> bool {
+                match name {
+                    $(stringify!($field) => match ($convert)(value) {
+                        Some(value) => {
+                            self.$field = Some(value);
+                            true
+                        }
+                        None => false,
+                    },)*
+                    _ => false,
+                }
+            }
+
+            fn uninterpreted(&mut self) -> &mut Vec<UninterpretedOption> {
+                &mut self.uninterpreted_option
+            }
+        }
+    )*};
+}
+
+options! {
+    FileOptions {
+        java_package: text,
+        java_outer_classname: text,
+        java_multiple_files: boolean,
+        java_generate_equals_and_hash: boolean,
+        java_string_check_utf8: boolean,
+        optimize_for: |value| enumeration(value, file_options::OptimizeMode::from_str_name),
+        go_package: text,
+        cc_generic_services: boolean,
+        java_generic_services: boolean,
+        py_generic_services: boolean,
+        php_generic_services: boolean,
+        deprecated: boolean,
+        cc_enable_arenas: boolean,
+        objc_class_prefix: text,
+        csharp_namespace: text,
+        swift_prefix: text,
+        php_class_prefix: text,
+        php_namespace: text,
+        php_metadata_namespace: text,
+        ruby_package: text,
+    }
+    MessageOptions {
+        message_set_wire_format: boolean,
+        no_standard_descriptor_accessor: boolean,
+        deprecated: boolean,
+    }
+    FieldOptions {
+        ctype: |value| enumeration(value, field_options::CType::from_str_name),
+        packed: boolean,
+        jstype: |value| enumeration(value, field_options::JsType::from_str_name),
+        lazy: boolean,
+        deprecated: boolean,
+        weak: boolean,
+    }
+    EnumOptions {
+        allow_alias: boolean,
+        deprecated: boolean,
+    }
+    EnumValueOptions {
+        deprecated: boolean,
+    }
+    ServiceOptions {
+        deprecated: boolean,
+    }
+    MethodOptions {
+        deprecated: boolean,
+        idempotency_level: |value| {
+            enumeration(value, method_options::IdempotencyLevel::from_str_name)
+        },
+    }
+}
+
+impl Options for OneofOptions {
+    fn set(&mut self, _name: &str, _value: &ast::MapValue) -> bool {
+        false
+    }
+
+    fn uninterpreted(&mut self) -> &mut Vec<UninterpretedOption> {
+        &mut self.uninterpreted_option
+    }
+}
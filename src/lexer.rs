@@ -9,7 +9,9 @@
 //! assert_eq!(first.1, Token::Syntax);
 //! ```
 
+use crate::span::Position;
 use logos::{Logos, Span};
+use std::borrow::Cow;
 use std::num::{IntErrorKind, ParseIntError};
 
 /// Categories of lexical errors produced by [`Lexer`].
@@ -18,6 +20,15 @@ pub enum LexicalErrorKind {
     #[default]
     InvalidToken,
     InvalidInteger(ParseIntError),
+    InvalidEscape,
+}
+
+impl ownable::traits::IntoOwned for LexicalErrorKind {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
 }
 
 impl From<ParseIntError> for LexicalErrorKind {
@@ -34,17 +45,19 @@ pub struct LexicalError<'a> {
     span: Span,
 }
 
-impl<'a> std::fmt::Display for LexicalError<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let line = self.input[..self.span.start]
-            .chars()
-            .filter(|&ch| ch == '\n')
-            .count()
-            + 1;
+impl<'a> LexicalError<'a> {
+    pub fn kind(&self) -> &LexicalErrorKind {
+        &self.kind
+    }
 
-        let column = self.span.start - self.input[..self.span.start].rfind("\n").unwrap_or(0);
+    pub fn span(&self) -> crate::span::Span {
+        self.span.clone().into()
+    }
+}
 
-        let position = format!("line {}, column {}", line, column);
+impl<'a> std::fmt::Display for LexicalError<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let position = Position::from_offset(self.input, self.span.start);
 
         match &self.kind {
             LexicalErrorKind::InvalidToken => write!(
@@ -63,15 +76,123 @@ impl<'a> std::fmt::Display for LexicalError<'a> {
                     _ => "unknown",
                 }
             )?,
+            LexicalErrorKind::InvalidEscape => write!(
+                f,
+                "Invalid escape sequence in string {} at {}",
+                &self.input[self.span.start..self.span.end],
+                position
+            )?,
         };
 
         Ok(())
     }
 }
 
-fn string_from_lexer<'a>(lex: &mut logos::Lexer<'a, Token<'a>>) -> &'a str {
+fn string_from_lexer<'a>(
+    lex: &mut logos::Lexer<'a, Token<'a>>,
+) -> Result<Cow<'a, [u8]>, LexicalErrorKind> {
+    let slice = lex.slice();
+    unescape(&slice[1..slice.len() - 1])
+}
+
+/// Decodes the escape sequences of a string literal body (without quotes).
+///
+/// Supports the simple C escapes, octal (`\101`), hex (`\x41`) and unicode (`\u00e9`,
+/// `\U0001F600`) escapes. Literals without a backslash are returned borrowed.
+fn unescape(content: &str) -> Result<Cow<'_, [u8]>, LexicalErrorKind> {
+    if !content.contains('\\') {
+        return Ok(Cow::Borrowed(content.as_bytes()));
+    }
+
+    fn digits(bytes: &[u8], radix: u32, max: usize) -> (u32, usize) {
+        bytes
+            .iter()
+            .take(max)
+            .map_while(|&byte| char::from(byte).to_digit(radix))
+            .fold((0, 0), |(value, count), digit| {
+                (value * radix + digit, count + 1)
+            })
+    }
+
+    let bytes = content.as_bytes();
+    let mut value = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let byte = bytes[index];
+        index += 1;
+
+        if byte != b'\\' {
+            value.push(byte);
+            continue;
+        }
+
+        let escape = *bytes.get(index).ok_or(LexicalErrorKind::InvalidEscape)?;
+        index += 1;
+
+        match escape {
+            b'a' => value.push(0x07),
+            b'b' => value.push(0x08),
+            b'f' => value.push(0x0C),
+            b'n' => value.push(b'\n'),
+            b'r' => value.push(b'\r'),
+            b't' => value.push(b'\t'),
+            b'v' => value.push(0x0B),
+            b'\\' | b'\'' | b'"' | b'?' => value.push(escape),
+            b'0'..=b'7' => {
+                let (code, count) = digits(&bytes[index - 1..], 8, 3);
+                let code = u8::try_from(code).map_err(|_| LexicalErrorKind::InvalidEscape)?;
+
+                value.push(code);
+                index += count - 1;
+            }
+            b'x' | b'X' => match digits(&bytes[index..], 16, 2) {
+                (_, 0) => return Err(LexicalErrorKind::InvalidEscape),
+                (code, count) => {
+                    value.push(code as u8);
+                    index += count;
+                }
+            },
+            b'u' | b'U' => {
+                let length = if escape == b'u' { 4 } else { 8 };
+                let (code, count) = digits(&bytes[index..], 16, length);
+                let ch = char::from_u32(code)
+                    .filter(|_| count == length)
+                    .ok_or(LexicalErrorKind::InvalidEscape)?;
+
+                value.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                index += count;
+            }
+            _ => return Err(LexicalErrorKind::InvalidEscape),
+        }
+    }
+
+    Ok(Cow::Owned(value))
+}
+
+/// Parses decimal, octal (`0` prefix) and hexadecimal (`0x` prefix) literals.
+///
+/// As in `protoc`, positive literals must fit into a `u64` and negative ones into an `i64`.
+fn integer_from_lexer<'a>(lex: &mut logos::Lexer<'a, Token<'a>>) -> Result<i128, ParseIntError> {
     let slice = lex.slice();
-    &slice[1..slice.len() - 1]
+    let (negative, digits) = match slice.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, slice),
+    };
+
+    let hex = digits.strip_prefix("0x").or(digits.strip_prefix("0X"));
+    let (radix, digits) = if let Some(hex) = hex {
+        (16, hex)
+    } else if let Some(octal) = digits.strip_prefix('0').filter(|octal| !octal.is_empty()) {
+        (8, octal)
+    } else {
+        (10, digits)
+    };
+
+    match negative {
+        true => i64::from_str_radix(&format!("-{digits}"), radix).map(i128::from),
+        false => u64::from_str_radix(digits, radix).map(i128::from),
+    }
 }
 
 /// Token kinds produced by the lexer.
@@ -100,6 +221,12 @@ pub enum Token<'a> {
     #[token(".")]
     Period,
 
+    #[token("/")]
+    Slash,
+
+    #[token("-")]
+    Minus,
+
     #[token("(")]
     OpenPth,
 
@@ -128,9 +255,14 @@ pub enum Token<'a> {
     #[token("false", |_| false)]
     Boolean(bool),
 
-    #[regex(r"-?[0-9]+", |lex| lex.slice().parse())]
-    #[regex(r"0x[0-9a-fA-F]{1,16}", |lex| i64::from_str_radix(&lex.slice()[2..], 16))]
-    Integer(i64),
+    #[regex(r"-?[0-9]+", integer_from_lexer)]
+    #[regex(r"-?0[xX][0-9a-fA-F]+", integer_from_lexer)]
+    Integer(i128),
+
+    #[regex(r"-?[0-9]+\.[0-9]*(?:[eE][+-]?[0-9]+)?", |lex| lex.slice().parse().ok())]
+    #[regex(r"-?[0-9]+[eE][+-]?[0-9]+", |lex| lex.slice().parse().ok())]
+    #[regex(r"-?\.[0-9]+(?:[eE][+-]?[0-9]+)?", |lex| lex.slice().parse().ok())]
+    Float(f64),
 
     #[token("to")]
     To,
@@ -141,6 +273,9 @@ pub enum Token<'a> {
     #[token("syntax")]
     Syntax,
 
+    #[token("edition")]
+    Edition,
+
     #[token("option")]
     Option,
 
@@ -150,6 +285,12 @@ pub enum Token<'a> {
     #[token("import")]
     Import,
 
+    #[token("public")]
+    Public,
+
+    #[token("weak")]
+    Weak,
+
     #[token("service")]
     Service,
 
@@ -192,9 +333,12 @@ pub enum Token<'a> {
     #[token("map")]
     Map,
 
-    #[regex(r#"'((?:[^'\n]|(?:\\\'))*)'"#, string_from_lexer)]
-    #[regex(r#""((?:[^"\n]|(?:\\\"))*)""#, string_from_lexer)]
-    String(&'a str),
+    #[token("group")]
+    Group,
+
+    #[regex(r#"'(?:[^'\\\n]|\\.)*'"#, string_from_lexer)]
+    #[regex(r#""(?:[^"\\\n]|\\.)*""#, string_from_lexer)]
+    String(Cow<'a, [u8]>),
 
     #[regex(r"[a-zA-Z_][a-zA-Z_0-9]*", priority = 0)]
     Ident(&'a str),
@@ -1,4 +1,4 @@
-//! Protocol Buffers (proto2/proto3) parser that produces a typed AST.
+//! Protocol Buffers (proto2, proto3 and editions) parser that produces a typed AST.
 //!
 //! # Examples
 //! ```rust
@@ -18,11 +18,23 @@ lalrpop_mod!(
 );
 
 pub mod ast;
+mod comments;
+pub mod cst;
+#[cfg(feature = "descriptor")]
+pub mod descriptor;
 pub mod lexer;
+pub mod loader;
 mod parser;
+pub mod printer;
+pub mod resolve;
+pub mod source;
+pub mod span;
+pub mod visit;
+#[cfg(feature = "well-known-types")]
+pub mod well_known;
 
 pub use ast::Root;
-pub use parser::{parse, ParseError, ParseResult};
+pub use parser::{parse, parse_recovering, GrammarError, ParseError, ParseErrorKind, ParseResult};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,377 @@
+//! Loading of `.proto` files together with everything they import.
+//!
+//! Sources are fetched through a [`SourceProvider`]. By default they are read from disk the way
+//! `protoc -I` does: an import path is looked up under each include directory in order and the
+//! first match wins. Files are identified by their canonical import path (such as
+//! `google/protobuf/any.proto`), never by their location, so a file reachable through several
+//! include directories is loaded once.
+//!
+//! # Examples
+//! ```rust,no_run
+//! use protobuf_ast_parser::loader::Loader;
+//!
+//! let files = Loader::new()
+//!     .include("proto")
+//!     .include("third_party")
+//!     .load(["service/api.proto"])
+//!     .expect("all imports resolve");
+//!
+//! for file in &files {
+//!     println!("{} ({} declarations)", file.name, file.ast.len());
+//! }
+//! ```
+
+use crate::ast::{self, Spanned};
+use crate::source::{canonical_name, is_relative, FileSystem, ReadError, SourceProvider};
+use crate::span::{Position, Span};
+use crate::{parse, ParseError};
+#[cfg(feature = "well-known-types")]
+use crate::{source::Chain, well_known::WellKnownTypes};
+use ownable::traits::IntoOwned;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Resolves and parses `.proto` files through a [`SourceProvider`].
+#[derive(Debug, Default, Clone)]
+pub struct Loader<P = FileSystem> {
+    provider: P,
+}
+
+impl Loader {
+    /// Creates a loader that reads from disk; add include directories with
+    /// [`include`](Self::include).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an include directory. Directories are searched in the order they were added;
+    /// without any, imports are resolved against the current directory.
+    pub fn include(mut self, path: impl Into<PathBuf>) -> Self {
+        self.provider.include(path);
+        self
+    }
+
+    pub fn include_paths(&self) -> &[PathBuf] {
+        self.provider.include_paths()
+    }
+}
+
+impl<P: SourceProvider> Loader<P> {
+    /// Creates a loader that fetches every file from `provider`.
+    pub fn with_provider(provider: P) -> Self {
+        Self { provider }
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Falls back to the bundled `google/protobuf/*.proto` files for imports the provider
+    /// does not have.
+    #[cfg(feature = "well-known-types")]
+    pub fn with_well_known_types(self) -> Loader<Chain<P, WellKnownTypes>> {
+        Loader::with_provider(self.provider.chain(WellKnownTypes))
+    }
+
+    /// Loads the files at the given import paths and, transitively, everything they import.
+    pub fn load<I, S>(&self, names: I) -> Result<FileSet, LoadError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut state = State::default();
+
+        for name in names {
+            self.visit(&mut state, name.as_ref(), None)?;
+        }
+
+        Ok(state.files)
+    }
+
+    fn visit(
+        &self,
+        state: &mut State,
+        name: &str,
+        imported_from: Option<ImportLocation>,
+    ) -> Result<(), LoadError> {
+        if !is_relative(name) {
+            return Err(LoadError::InvalidPath {
+                name: name.to_string(),
+                imported_from,
+            });
+        }
+
+        let name = &canonical_name(name);
+        if state.files.contains(name) {
+            return Ok(());
+        }
+
+        if let Some(index) = state.stack.iter().position(|entry| entry == name) {
+            let mut chain = state.stack[index..].to_vec();
+            chain.push(name.to_string());
+
+            return Err(LoadError::Cycle {
+                chain,
+                imported_from: imported_from.expect("a cycle is closed by an import"),
+            });
+        }
+
+        let (path, source) = self.read(name, imported_from)?;
+        let ast = match parse(&source) {
+            Ok(ast) => ast.into_owned(),
+            Err(error) => {
+                return Err(LoadError::Parse {
+                    name: name.to_string(),
+                    error: Box::new(error.into_owned()),
+                });
+            }
+        };
+
+        state.stack.push(name.to_string());
+
+        for import in ast.iter().filter_map(|entry| match entry {
+            ast::RootEntry::Import(import) => Some(import),
+            _ => None,
+        }) {
+            let location = ImportLocation {
+                file: name.to_string(),
+                span: import.span(),
+                position: import.span().start_position(&source),
+            };
+            let dependency = String::from_utf8_lossy(&import.path.value);
+
+            match self.visit(state, &dependency, Some(location)) {
+                // A weak import may be absent; files it imports in turn may not.
+                Err(LoadError::NotFound { name, .. })
+                    if import.kind == ast::ImportKind::Weak
+                        && name == canonical_name(&dependency) => {}
+                result => result?,
+            }
+        }
+
+        state.stack.pop();
+        state.files.push(File {
+            name: name.to_string(),
+            path,
+            source,
+            ast,
+        });
+
+        Ok(())
+    }
+
+    fn read(
+        &self,
+        name: &str,
+        imported_from: Option<ImportLocation>,
+    ) -> Result<(PathBuf, String), LoadError> {
+        match self.provider.read(name) {
+            Ok(Some(source)) => Ok((source.path, source.text)),
+            Ok(None) => Err(LoadError::NotFound {
+                name: name.to_string(),
+                imported_from,
+            }),
+            Err(ReadError { path, error }) => Err(LoadError::Io {
+                name: name.to_string(),
+                path,
+                error,
+            }),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    files: FileSet,
+    /// Files whose imports are being loaded, outermost first.
+    stack: Vec<String>,
+}
+
+/// Parsed `.proto` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct File {
+    /// Canonical import path.
+    pub name: String,
+    /// Location reported by the provider the source was read from.
+    pub path: PathBuf,
+    pub source: String,
+    pub ast: ast::Root<'static>,
+}
+
+/// Files returned by [`Loader::load`], ordered so that every file comes after its imports.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileSet {
+    files: Vec<File>,
+    /// Index into `files` by import path.
+    index: HashMap<String, usize>,
+}
+
+impl FileSet {
+    fn push(&mut self, file: File) {
+        self.index.insert(file.name.clone(), self.files.len());
+        self.files.push(file);
+    }
+
+    /// Returns the file with the given import path.
+    pub fn get(&self, name: &str) -> Option<&File> {
+        let index = *self.index.get(&canonical_name(name))?;
+        Some(&self.files[index])
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut File> {
+        let index = *self.index.get(&canonical_name(name))?;
+        Some(&mut self.files[index])
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|file| file.name.as_str())
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, File> {
+        self.files.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, File> {
+        self.files.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl From<FileSet> for Vec<File> {
+    fn from(value: FileSet) -> Self {
+        value.files
+    }
+}
+
+impl IntoIterator for FileSet {
+    type Item = File;
+    type IntoIter = std::vec::IntoIter<File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
+
+impl<'s> IntoIterator for &'s FileSet {
+    type Item = &'s File;
+    type IntoIter = std::slice::Iter<'s, File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.iter()
+    }
+}
+
+/// `import` statement that caused a file to be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportLocation {
+    /// Canonical import path of the importing file.
+    pub file: String,
+    pub span: Span,
+    pub position: Position,
+}
+
+impl std::fmt::Display for ImportLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at {}", self.file, self.position)
+    }
+}
+
+/// Error raised while loading a [`FileSet`].
+#[derive(Debug)]
+pub enum LoadError {
+    /// The provider has no such file; `imported_from` is `None` for files passed to
+    /// [`Loader::load`] directly. Missing targets of `import weak` are skipped instead.
+    NotFound {
+        name: String,
+        imported_from: Option<ImportLocation>,
+    },
+    /// The import path is absolute or contains `..`, so it could name a file outside the
+    /// include directories; `imported_from` is `None` for files passed to [`Loader::load`].
+    InvalidPath {
+        name: String,
+        imported_from: Option<ImportLocation>,
+    },
+    /// The provider failed to read the file from `path`.
+    Io {
+        name: String,
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The file is not valid `.proto` source.
+    Parse {
+        name: String,
+        error: Box<ParseError<'static>>,
+    },
+    /// Files import each other; `chain` starts and ends with the same file.
+    Cycle {
+        chain: Vec<String>,
+        imported_from: ImportLocation,
+    },
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LoadError::NotFound {
+                name,
+                imported_from,
+            } => {
+                write!(f, "file \"{}\" not found", name)?;
+
+                match imported_from {
+                    Some(location) => write!(f, " (imported from {})", location),
+                    None => Ok(()),
+                }
+            }
+            LoadError::InvalidPath {
+                name,
+                imported_from,
+            } => {
+                write!(f, "import path \"{}\" leaves the include directories", name)?;
+
+                match imported_from {
+                    Some(location) => write!(f, " (imported from {})", location),
+                    None => Ok(()),
+                }
+            }
+            LoadError::Io { name, path, error } => write!(
+                f,
+                "failed to read \"{}\" from {}: {}",
+                name,
+                path.display(),
+                error
+            ),
+            LoadError::Parse { name, error } => write!(f, "in \"{}\": {}", name, error),
+            LoadError::Cycle {
+                chain,
+                imported_from,
+            } => write!(
+                f,
+                "import cycle {} (imported from {})",
+                chain.join(" -> "),
+                imported_from
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { error, .. } => Some(error),
+            LoadError::Parse { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -1,14 +1,289 @@
-use crate::{ast, lexer, proto};
+use crate::lexer::{LexicalError, LexicalErrorKind, Token};
+use crate::span::{Position, Span};
+use crate::{ast, comments, lexer, proto};
+use ownable::IntoOwned;
+use std::borrow::Cow;
+use std::num::IntErrorKind;
 
-/// Parse error type returned by [`parse`].
-pub type ParseError<'a> =
-    lalrpop_util::ParseError<usize, lexer::Token<'a>, lexer::LexicalError<'a>>;
+type LalrpopError<'a> = lalrpop_util::ParseError<usize, Token<'a>, GrammarError<'a>>;
+
+/// Error raised by the lexer or a grammar action, before it is resolved against the source.
+///
+/// This is the error type of the generated [`proto`](crate::proto) parsers, which is only
+/// needed to drive them directly instead of through [`parse`].
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::{lexer::Lexer, proto::RootParser, GrammarError};
+///
+/// let source = "message Empty {}";
+/// let tokens = Lexer::new(source).map(|token| token.map_err(GrammarError::from));
+/// let mut errors = Vec::new();
+///
+/// let ast = RootParser::new().parse(source, &mut errors, tokens).unwrap();
+/// assert_eq!(ast.len(), 1);
+/// assert!(errors.is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrammarError<'a> {
+    pub kind: ParseErrorKind<'a>,
+    pub span: Span,
+}
+
+impl<'a> GrammarError<'a> {
+    pub fn new(kind: ParseErrorKind<'a>, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Wraps the error so a grammar action can record it and keep parsing.
+    pub fn recovery(self) -> lalrpop_util::ErrorRecovery<usize, Token<'a>, Self> {
+        lalrpop_util::ErrorRecovery {
+            error: lalrpop_util::ParseError::User { error: self },
+            dropped_tokens: vec![],
+        }
+    }
+}
+
+impl<'a> From<LexicalError<'a>> for GrammarError<'a> {
+    fn from(error: LexicalError<'a>) -> Self {
+        Self::new(ParseErrorKind::Lexical(error.kind().clone()), error.span())
+    }
+}
+
+/// What went wrong in a [`ParseError`].
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub enum ParseErrorKind<'a> {
+    /// The lexer could not produce a token.
+    Lexical(LexicalErrorKind),
+    /// A token appeared where the grammar does not allow it.
+    UnrecognizedToken {
+        token: Cow<'a, str>,
+        #[ownable(clone)]
+        expected: Vec<String>,
+    },
+    /// The input ended in the middle of a declaration.
+    UnrecognizedEof {
+        #[ownable(clone)]
+        expected: Vec<String>,
+    },
+    /// A token was found after the parser expected the input to end.
+    ExtraToken { token: Cow<'a, str> },
+    /// `syntax` names something other than `proto2` or `proto3`.
+    UnknownSyntax { syntax: Cow<'a, str> },
+    /// `edition` names an edition this parser does not support.
+    UnknownEdition { edition: Cow<'a, str> },
+    /// A `map` key type that is not an integral, `bool` or `string` scalar.
+    InvalidMapKey { r#type: Cow<'a, str> },
+}
+
+/// Parse error with its location resolved against the parsed source.
+///
+/// The [`Display`](std::fmt::Display) implementation renders a message followed by the
+/// offending source line and a caret under the reported span.
+///
+/// Errors borrow the parsed source; [`IntoOwned`](ownable::traits::IntoOwned) detaches them.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::{parse, ParseErrorKind};
+///
+/// let error = parse("message User { string name 1; }").unwrap_err();
+/// assert_eq!(error.position.line, 1);
+/// assert_eq!(error.position.column, 28);
+/// assert!(matches!(
+///     error.kind,
+///     ParseErrorKind::UnrecognizedToken { ref token, ref expected }
+///         if token == "1" && expected == &["\"=\""]
+/// ));
+/// ```
+#[derive(Debug, Clone, PartialEq, IntoOwned)]
+pub struct ParseError<'a> {
+    pub kind: ParseErrorKind<'a>,
+    pub span: Span,
+    pub position: Position,
+    /// Full source line containing the start of `span`, without the line terminator.
+    pub source_line: Cow<'a, str>,
+}
+
+impl<'a> ParseError<'a> {
+    pub(crate) fn new(source: &'a str, error: LalrpopError<'a>) -> Self {
+        let (kind, span) = match error {
+            lalrpop_util::ParseError::InvalidToken { location } => (
+                ParseErrorKind::Lexical(LexicalErrorKind::InvalidToken),
+                Span::new(location, location),
+            ),
+            lalrpop_util::ParseError::UnrecognizedEof { location, expected } => (
+                ParseErrorKind::UnrecognizedEof {
+                    expected: describe_expected(expected),
+                },
+                Span::new(location, location),
+            ),
+            lalrpop_util::ParseError::UnrecognizedToken {
+                token: (start, _, end),
+                expected,
+            } => (
+                ParseErrorKind::UnrecognizedToken {
+                    token: Cow::from(&source[start..end]),
+                    expected: describe_expected(expected),
+                },
+                Span::new(start, end),
+            ),
+            lalrpop_util::ParseError::ExtraToken {
+                token: (start, _, end),
+            } => (
+                ParseErrorKind::ExtraToken {
+                    token: Cow::from(&source[start..end]),
+                },
+                Span::new(start, end),
+            ),
+            lalrpop_util::ParseError::User { error } => (error.kind, error.span),
+        };
+
+        let line_start = source[..span.start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |index| span.start + index);
+
+        Self {
+            kind,
+            span,
+            position: span.start_position(source),
+            source_line: Cow::from(source[line_start..line_end].trim_end_matches('\r')),
+        }
+    }
+
+    /// Source text of the offending token, if the error points at one.
+    pub fn token(&self) -> std::option::Option<&str> {
+        match &self.kind {
+            ParseErrorKind::UnrecognizedToken { token, .. }
+            | ParseErrorKind::ExtraToken { token } => Some(token),
+            _ => None,
+        }
+    }
+
+    /// Human-readable names of the tokens the parser would have accepted.
+    pub fn expected(&self) -> &[String] {
+        match &self.kind {
+            ParseErrorKind::UnrecognizedToken { expected, .. }
+            | ParseErrorKind::UnrecognizedEof { expected } => expected,
+            _ => &[],
+        }
+    }
+}
+
+impl<'a> std::fmt::Display for ParseError<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.kind {
+            ParseErrorKind::Lexical(LexicalErrorKind::InvalidToken) => {
+                write!(f, "invalid token")?
+            }
+            ParseErrorKind::Lexical(LexicalErrorKind::InvalidInteger(inner)) => write!(
+                f,
+                "invalid integer ({})",
+                match inner.kind() {
+                    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => "overflow",
+                    _ => "malformed",
+                }
+            )?,
+            ParseErrorKind::Lexical(LexicalErrorKind::InvalidEscape) => {
+                write!(f, "invalid escape sequence")?
+            }
+            ParseErrorKind::UnrecognizedToken { token, .. } => {
+                write!(f, "unexpected \"{}\"", token)?
+            }
+            ParseErrorKind::UnrecognizedEof { .. } => write!(f, "unexpected end of file")?,
+            ParseErrorKind::ExtraToken { token } => write!(f, "extra token \"{}\"", token)?,
+            ParseErrorKind::UnknownSyntax { syntax } => write!(f, "unknown syntax {}", syntax)?,
+            ParseErrorKind::UnknownEdition { edition } => write!(f, "unknown edition {}", edition)?,
+            ParseErrorKind::InvalidMapKey { r#type } => {
+                write!(f, "invalid map key type \"{}\"", r#type)?
+            }
+        }
+
+        write!(f, " at {}", self.position)?;
+
+        match self.expected() {
+            [] => {}
+            [single] => write!(f, ", expected {}", single)?,
+            expected => write!(f, ", expected one of {}", expected.join(", "))?,
+        }
+
+        let gutter = " ".repeat(self.position.line.to_string().len());
+        let start = self
+            .source_line
+            .char_indices()
+            .nth(self.position.column - 1)
+            .map_or(self.source_line.len(), |(index, _)| index);
+        let indent: String = self.source_line[..start]
+            .chars()
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = self.source_line[start..]
+            .char_indices()
+            .take_while(|&(index, _)| index < self.span.len())
+            .count()
+            .max(1);
+
+        write!(
+            f,
+            "\n{gutter} |\n{} | {}\n{gutter} | {}{}",
+            self.position.line,
+            self.source_line,
+            indent,
+            "^".repeat(width)
+        )
+    }
+}
+
+impl<'a> std::error::Error for ParseError<'a> {}
+
+/// Turns LALRPOP terminal names into the source text they stand for.
+fn describe_expected(expected: Vec<String>) -> Vec<String> {
+    expected
+        .into_iter()
+        .map(|terminal| {
+            let text = match terminal.as_str() {
+                "SingleLineComment" | "MultiLineComment" => "comment",
+                "Eq" => "\"=\"",
+                "Colon" => "\":\"",
+                "Semicolon" => "\";\"",
+                "Comma" => "\",\"",
+                "Period" => "\".\"",
+                "Slash" => "\"/\"",
+                "OpenPth" => "\"(\"",
+                "ClosePth" => "\")\"",
+                "OpenBracket" => "\"[\"",
+                "CloseBracket" => "\"]\"",
+                "OpenBrace" => "\"{\"",
+                "CloseBrace" => "\"}\"",
+                "OpenAngle" => "\"<\"",
+                "CloseAngle" => "\">\"",
+                "Boolean" => "boolean",
+                "Integer" => "integer",
+                "Float" => "float",
+                "String" => "string",
+                "Ident" => "identifier",
+                // Keywords are already spelled as quoted literals.
+                _ => return terminal,
+            };
+
+            text.to_string()
+        })
+        .fold(Vec::new(), |mut expected, text| {
+            if !expected.contains(&text) {
+                expected.push(text);
+            }
+            expected
+        })
+}
 
 /// Result alias for parsing `.proto` sources.
 pub type ParseResult<'a> = Result<ast::Root<'a>, ParseError<'a>>;
 
 /// Parse a Protocol Buffers source string into an AST.
 ///
+/// Parsing stops at the first error; use [`parse_recovering`] to collect every error instead.
+///
 /// # Examples
 /// ```rust
 /// use protobuf_ast_parser::parse;
@@ -19,8 +294,151 @@ pub type ParseResult<'a> = Result<ast::Root<'a>, ParseError<'a>>;
 /// ```
 #[allow(clippy::needless_lifetimes)]
 pub fn parse<'a>(data: &'a str) -> ParseResult<'a> {
-    let lexer = lexer::Lexer::new(data);
+    let lexer = lexer::Lexer::new(data).map(|token| token.map_err(GrammarError::from));
     let parser = proto::RootParser::new();
+    let mut recovered = Vec::new();
+
+    let result = parser.parse(data, &mut recovered, lexer);
+
+    // The grammar always recovers, so the first recorded error is the one a strict parser
+    // would have stopped at.
+    match recovered.into_iter().next() {
+        Some(recovery) => Err(ParseError::new(data, recovery.error)),
+        None => result
+            .map(|mut ast| {
+                comments::attach(data, &mut ast);
+                ast
+            })
+            .map_err(|error| ParseError::new(data, error)),
+    }
+}
+
+/// Parse a Protocol Buffers source string, skipping over broken statements.
+///
+/// Invalid tokens are dropped and malformed statements are skipped up to the next `;`, `}`
+/// or statement start, so the returned AST holds every declaration that could be parsed.
+/// Blocks still open at the end of the input are closed there, so a declaration that is
+/// being typed is kept with the entries parsed so far, and the missing `}` is reported as an
+/// unexpected end of file. Errors are returned in source order.
+///
+/// # Examples
+/// ```rust
+/// use protobuf_ast_parser::parse_recovering;
+///
+/// let source = "message User { string name 1; int32 age = 2; }\nmessage Empty {}";
+/// let (ast, errors) = parse_recovering(source);
+/// assert_eq!(ast.len(), 2);
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].position.column, 28);
+///
+/// let (ast, errors) = parse_recovering("message User {}\nmessage Draft { int32 id = 1;");
+/// assert_eq!(ast.len(), 2);
+/// assert_eq!(errors[0].to_string().lines().next(), Some(
+///     "unexpected end of file at line 2, column 30, expected \"}\""
+/// ));
+/// ```
+#[allow(clippy::needless_lifetimes)]
+pub fn parse_recovering<'a>(data: &'a str) -> (ast::Root<'a>, Vec<ParseError<'a>>) {
+    let mut lexical = Vec::new();
+    let mut closed = 0;
+    let lexer = SkipInvalid {
+        inner: lexer::Lexer::new(data),
+        errors: &mut lexical,
+        end: data.len(),
+        depth: 0,
+        closed: &mut closed,
+    };
+    let parser = proto::RootParser::new();
+    let mut recovered = Vec::new();
+
+    let result = parser.parse(data, &mut recovered, lexer);
+
+    let mut errors: Vec<_> = lexical
+        .into_iter()
+        .map(|error| {
+            ParseError::new(
+                data,
+                lalrpop_util::ParseError::User {
+                    error: GrammarError::from(error),
+                },
+            )
+        })
+        .chain(recovered.into_iter().map(|recovery| match recovery.error {
+            // A `}` inserted by `SkipInvalid` stands for the end of the input.
+            lalrpop_util::ParseError::UnrecognizedToken {
+                token: (location, _, _),
+                expected,
+            } if location == data.len() => ParseError::new(
+                data,
+                lalrpop_util::ParseError::UnrecognizedEof { location, expected },
+            ),
+            error => ParseError::new(data, error),
+        }))
+        .collect();
+
+    if closed > 0 && !errors.iter().any(|error| error.span.start == data.len()) {
+        let error = lalrpop_util::ParseError::UnrecognizedEof {
+            location: data.len(),
+            expected: vec!["CloseBrace".to_string()],
+        };
+        errors.push(ParseError::new(data, error));
+    }
+
+    errors.sort_by_key(|error| error.span.start);
+    // Every inserted `}` may fail on its own; one error at the end of the input is enough.
+    errors.dedup_by(|error, previous| {
+        error.span.start == data.len() && previous.span.start == data.len()
+    });
+
+    match result {
+        Ok(mut ast) => {
+            comments::attach(data, &mut ast);
+            (ast, errors)
+        }
+        Err(error) => {
+            errors.push(ParseError::new(data, error));
+            (vec![], errors)
+        }
+    }
+}
+
+/// Lexer adapter that records lexical errors and drops the offending input.
+///
+/// At the end of the input it inserts a `}` for every brace still open, counting them in
+/// `closed`.
+struct SkipInvalid<'a, 'e> {
+    inner: lexer::Lexer<'a>,
+    errors: &'e mut Vec<LexicalError<'a>>,
+    /// Offset of the end of the input.
+    end: usize,
+    depth: usize,
+    closed: &'e mut usize,
+}
+
+impl<'a, 'e> Iterator for SkipInvalid<'a, 'e> {
+    type Item = Result<(usize, Token<'a>, usize), GrammarError<'a>>;
+
+    fn next(&mut self) -> std::option::Option<Self::Item> {
+        loop {
+            match self.inner.next() {
+                Some(Ok(token)) => {
+                    match token.1 {
+                        Token::OpenBrace => self.depth += 1,
+                        Token::CloseBrace => self.depth = self.depth.saturating_sub(1),
+                        _ => {}
+                    }
+
+                    return Some(Ok(token));
+                }
+                Some(Err(error)) => self.errors.push(error),
+                None if self.depth > 0 => {
+                    self.depth -= 1;
+                    *self.closed += 1;
 
-    parser.parse(data, lexer)
+                    return Some(Ok((self.end, Token::CloseBrace, self.end)));
+                }
+                None => return None,
+            }
+        }
+    }
 }
@@ -0,0 +1,699 @@
+//! Rendering of ASTs back into `.proto` source.
+//!
+//! Comment entries are printed where they appear in the tree. For parsed files, the
+//! [`AttachedComments`] of each declaration restore trailing comments on the declaration line
+//! and the blank lines that separate detached comments.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::{parse, printer};
+//!
+//! let source = "syntax = \"proto3\"; message User { string name = 1; int64 id = 2; }";
+//! let ast = parse(source).unwrap();
+//! let options = printer::PrintOptions {
+//!     align_field_numbers: true,
+//!     ..Default::default()
+//! };
+//! assert_eq!(
+//!     printer::print_with(&ast, &options),
+//!     "syntax = \"proto3\";\n\nmessage User {\n  string name = 1;\n  int64 id    = 2;\n}\n"
+//! );
+//! ```
+
+use crate::ast::{self, AttachedComments, CommentType};
+use crate::{comments, lexer};
+
+/// Layout settings for [`print_with`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintOptions {
+    /// Text inserted once per nesting level.
+    pub indent: String,
+    /// Pads field and enum value names so the `=` signs of a block line up.
+    pub align_field_numbers: bool,
+    /// Whether comment entries are printed.
+    pub comments: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            indent: "  ".to_string(),
+            align_field_numbers: false,
+            comments: true,
+        }
+    }
+}
+
+/// Renders a file with the default [`PrintOptions`].
+pub fn print(root: &ast::Root) -> String {
+    print_with(root, &PrintOptions::default())
+}
+
+/// Renders a file as `.proto` source.
+///
+/// Parsing the output yields the same tree, apart from spans and whitespace inside
+/// concatenated string literals and block comments, which is normalized.
+pub fn print_with(root: &ast::Root, options: &PrintOptions) -> String {
+    let printer = Printer { options };
+    let entries = root.iter().map(|entry| printer.root_entry(entry)).collect();
+
+    printer.body(None, entries).1
+}
+
+impl std::fmt::Display for ast::RootEntry<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let options = PrintOptions::default();
+        let printer = Printer { options: &options };
+        let (_, text) = printer.body(None, vec![printer.root_entry(self)]);
+
+        f.write_str(text.trim_end_matches('\n'))
+    }
+}
+
+impl std::fmt::Display for ast::MapValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let options = PrintOptions::default();
+        f.write_str(&Printer { options: &options }.value(self))
+    }
+}
+
+/// Kind of statement, used to separate groups of statements with blank lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Syntax,
+    Package,
+    Import,
+    Option,
+    /// Single-statement declaration inside a block (field, enum value, `reserved`, ...).
+    Member,
+    /// Declaration with a body, always surrounded by blank lines.
+    Block,
+}
+
+enum Text {
+    Plain(String),
+    /// Declaration whose `=` sign is aligned with its neighbours: `head`, padding, `tail`.
+    Aligned {
+        head: String,
+        tail: String,
+    },
+}
+
+struct Stmt<'n> {
+    kind: Kind,
+    text: Text,
+    comments: std::option::Option<&'n AttachedComments>,
+}
+
+enum Entry<'n> {
+    Comment(&'n ast::Comment<'n>),
+    Stmt(Stmt<'n>),
+}
+
+enum Piece {
+    Blank,
+    Comment(String),
+    Stmt {
+        text: Text,
+        trailing: std::option::Option<String>,
+    },
+}
+
+struct Printer<'o> {
+    options: &'o PrintOptions,
+}
+
+impl Printer<'_> {
+    /// Lays out the entries of a file or block, unindented.
+    ///
+    /// Returns the comment to put after the opening `{` of the block, if any, and the lines.
+    fn body(
+        &self,
+        header: std::option::Option<&AttachedComments>,
+        entries: Vec<Entry<'_>>,
+    ) -> (std::option::Option<String>, String) {
+        let mut pieces = vec![];
+        let mut pending = vec![];
+        let mut inline = None;
+        let mut prev: std::option::Option<(Kind, std::option::Option<&AttachedComments>)> = None;
+
+        for entry in entries.into_iter().map(Some).chain([None]) {
+            let stmt = match entry {
+                Some(Entry::Comment(comment)) => {
+                    if self.options.comments {
+                        pending.push(comment);
+                    }
+                    continue;
+                }
+                Some(Entry::Stmt(stmt)) => Some(stmt),
+                None => None,
+            };
+            let mut rest = &pending[..];
+
+            // Bodies of blocks start after `{`, so comments after `}` never trail a block.
+            let trailing = match prev {
+                None => header.and_then(|comments| comments.trailing.as_deref()),
+                Some((Kind::Block, _)) => None,
+                Some((_, comments)) => comments.and_then(|comments| comments.trailing.as_deref()),
+            };
+            if let Some(count) = trailing.and_then(|trailing| matching(rest, trailing)) {
+                match &rest[..count] {
+                    [comment] if comment.r#type == CommentType::SingleLine => {
+                        let text = comment.source.trim_end().to_string();
+
+                        match pieces.last_mut() {
+                            Some(Piece::Stmt { trailing, .. }) => *trailing = Some(text),
+                            _ => inline = Some(text),
+                        }
+                    }
+                    comments => {
+                        pieces.extend(comments.iter().map(|comment| comment_piece(comment)));
+                        pieces.push(Piece::Blank);
+                    }
+                }
+                rest = &rest[count..];
+            }
+
+            match (prev, &stmt) {
+                (Some((prev, _)), Some(stmt)) if prev != stmt.kind || prev == Kind::Block => {
+                    pieces.push(Piece::Blank)
+                }
+                (Some(_), None) if !rest.is_empty() => pieces.push(Piece::Blank),
+                _ => {}
+            }
+
+            if let Some(comments) = stmt.as_ref().and_then(|stmt| stmt.comments) {
+                let detached = comments
+                    .leading_detached
+                    .first()
+                    .and_then(|paragraph| matching(rest, paragraph));
+
+                // Comments right below another declaration would trail it instead.
+                if detached.is_some() && (prev.is_some() || header.is_some()) {
+                    pieces.push(Piece::Blank);
+                }
+
+                for paragraph in &comments.leading_detached {
+                    let Some(count) = matching(rest, paragraph) else {
+                        break;
+                    };
+
+                    pieces.extend(rest[..count].iter().map(|comment| comment_piece(comment)));
+                    pieces.push(Piece::Blank);
+                    rest = &rest[count..];
+                }
+            }
+
+            pieces.extend(rest.iter().map(|comment| comment_piece(comment)));
+            pending.clear();
+
+            if let Some(stmt) = stmt {
+                prev = Some((stmt.kind, stmt.comments));
+                pieces.push(Piece::Stmt {
+                    text: stmt.text,
+                    trailing: None,
+                });
+            }
+        }
+
+        (inline, self.render(pieces))
+    }
+
+    fn render(&self, pieces: Vec<Piece>) -> String {
+        let width = pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Stmt {
+                    text: Text::Aligned { head, .. },
+                    ..
+                } if self.options.align_field_numbers => Some(head.chars().count()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+
+        let mut out = String::new();
+        let mut blank = false;
+
+        for piece in pieces {
+            let (text, trailing) = match piece {
+                Piece::Blank => {
+                    blank = true;
+                    continue;
+                }
+                Piece::Comment(text) => (text, None),
+                Piece::Stmt {
+                    text: Text::Plain(text),
+                    trailing,
+                } => (text, trailing),
+                Piece::Stmt {
+                    text: Text::Aligned { head, tail },
+                    trailing,
+                } => (format!("{head:width$} {tail}"), trailing),
+            };
+
+            if std::mem::take(&mut blank) {
+                out.push('\n');
+            }
+
+            out.push_str(&text);
+            if let Some(trailing) = trailing {
+                out.push(' ');
+                out.push_str(&trailing);
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn indent(&self, text: &str) -> String {
+        text.split_inclusive('\n')
+            .map(|line| match line {
+                "\n" => line.to_string(),
+                line => format!("{}{}", self.options.indent, line),
+            })
+            .collect()
+    }
+
+    fn block(&self, head: String, comments: &AttachedComments, entries: Vec<Entry<'_>>) -> Text {
+        let (inline, body) = self.body(Some(comments), entries);
+        let mut text = head + " {";
+
+        if let Some(inline) = &inline {
+            text.push(' ');
+            text.push_str(inline);
+        }
+
+        if !body.is_empty() || inline.is_some() {
+            text.push('\n');
+            text.push_str(&self.indent(&body));
+        }
+
+        text.push('}');
+        Text::Plain(text)
+    }
+
+    fn root_entry<'n>(&self, entry: &'n ast::RootEntry) -> Entry<'n> {
+        let (kind, text) = match entry {
+            ast::RootEntry::Comment(comment) => return Entry::Comment(comment),
+            ast::RootEntry::Syntax(syntax) => {
+                let keyword = match syntax.keyword {
+                    ast::SyntaxKeyword::Syntax => "syntax",
+                    ast::SyntaxKeyword::Edition => "edition",
+                };
+
+                (
+                    Kind::Syntax,
+                    format!("{} = {};", keyword, string(&syntax.value)),
+                )
+            }
+            ast::RootEntry::Package(package) => {
+                (Kind::Package, format!("package {};", package.path))
+            }
+            ast::RootEntry::Import(import) => {
+                let kind = match import.kind {
+                    ast::ImportKind::Default => "",
+                    ast::ImportKind::Public => "public ",
+                    ast::ImportKind::Weak => "weak ",
+                };
+
+                (
+                    Kind::Import,
+                    format!("import {}{};", kind, string(&import.path)),
+                )
+            }
+            ast::RootEntry::Option(option) => return self.option(option),
+            ast::RootEntry::Service(service) => return self.service(service),
+            ast::RootEntry::Message(message) => return self.message(message),
+            ast::RootEntry::Extend(extend) => return self.extend(extend),
+            ast::RootEntry::Enum(r#enum) => return self.r#enum(r#enum),
+        };
+
+        Entry::Stmt(Stmt {
+            kind,
+            text: Text::Plain(text),
+            comments: None,
+        })
+    }
+
+    fn option<'n>(&self, option: &ast::Option) -> Entry<'n> {
+        Entry::Stmt(Stmt {
+            kind: Kind::Option,
+            text: Text::Plain(format!(
+                "option {} = {};",
+                option.name,
+                self.value(&option.value)
+            )),
+            comments: None,
+        })
+    }
+
+    fn service<'n>(&self, service: &'n ast::Service) -> Entry<'n> {
+        let entries = service
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::ServiceEntry::Comment(comment) => Entry::Comment(comment),
+                ast::ServiceEntry::Option(option) => self.option(option),
+                ast::ServiceEntry::Rpc(rpc) => self.rpc(rpc),
+            })
+            .collect();
+
+        Entry::Stmt(Stmt {
+            kind: Kind::Block,
+            text: self.block(
+                format!("service {}", service.ident),
+                &service.comments,
+                entries,
+            ),
+            comments: Some(&service.comments),
+        })
+    }
+
+    fn rpc<'n>(&self, rpc: &'n ast::Rpc) -> Entry<'n> {
+        let (request, reply) = match rpc.stream {
+            ast::RpcStream::None => ("", ""),
+            ast::RpcStream::ServerBound => ("stream ", ""),
+            ast::RpcStream::ClientBound => ("", "stream "),
+            ast::RpcStream::Bidirectional => ("stream ", "stream "),
+        };
+        let head = format!(
+            "rpc {}({}{}) returns ({}{})",
+            rpc.ident, request, rpc.request, reply, rpc.reply
+        );
+
+        if rpc.entries.is_empty() {
+            return Entry::Stmt(Stmt {
+                kind: Kind::Member,
+                text: Text::Plain(head + ";"),
+                comments: Some(&rpc.comments),
+            });
+        }
+
+        let entries = rpc
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::RpcEntry::Comment(comment) => Entry::Comment(comment),
+                ast::RpcEntry::Option(option) => self.option(option),
+            })
+            .collect();
+
+        Entry::Stmt(Stmt {
+            kind: Kind::Block,
+            text: self.block(head, &rpc.comments, entries),
+            comments: Some(&rpc.comments),
+        })
+    }
+
+    fn message<'n>(&self, message: &'n ast::Message) -> Entry<'n> {
+        Entry::Stmt(Stmt {
+            kind: Kind::Block,
+            text: self.block(
+                format!("message {}", message.ident),
+                &message.comments,
+                self.message_entries(&message.entries),
+            ),
+            comments: Some(&message.comments),
+        })
+    }
+
+    fn message_entries<'n>(&self, entries: &'n [ast::MessageEntry]) -> Vec<Entry<'n>> {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                ast::MessageEntry::Comment(comment) => Entry::Comment(comment),
+                ast::MessageEntry::Option(option) => self.option(option),
+                ast::MessageEntry::Field(field) => self.field(field),
+                ast::MessageEntry::Group(group) => self.group(group),
+                ast::MessageEntry::OneOf(one_of) => self.one_of(one_of),
+                ast::MessageEntry::Message(message) => self.message(message),
+                ast::MessageEntry::Extend(extend) => self.extend(extend),
+                ast::MessageEntry::Enum(r#enum) => self.r#enum(r#enum),
+                ast::MessageEntry::ReservedIndices(indices) => {
+                    member(format!("reserved {};", ranges(indices)))
+                }
+                ast::MessageEntry::ReservedIdents(idents) => member(reserved_idents(idents)),
+                ast::MessageEntry::Extensions(extensions) => {
+                    member(format!("extensions {};", ranges(extensions)))
+                }
+            })
+            .collect()
+    }
+
+    fn field<'n>(&self, field: &'n ast::Field) -> Entry<'n> {
+        Entry::Stmt(Stmt {
+            kind: Kind::Member,
+            text: Text::Aligned {
+                head: format!(
+                    "{}{} {}",
+                    modifier(&field.modifier),
+                    field.r#type,
+                    field.ident
+                ),
+                tail: format!("= {}{};", field.index, self.field_options(&field.options)),
+            },
+            comments: Some(&field.comments),
+        })
+    }
+
+    fn group<'n>(&self, group: &'n ast::Group) -> Entry<'n> {
+        let head = format!(
+            "{}group {} = {}{}",
+            modifier(&group.modifier),
+            group.ident,
+            group.index,
+            self.field_options(&group.options)
+        );
+
+        Entry::Stmt(Stmt {
+            kind: Kind::Block,
+            text: self.block(head, &group.comments, self.message_entries(&group.entries)),
+            comments: Some(&group.comments),
+        })
+    }
+
+    fn one_of<'n>(&self, one_of: &'n ast::OneOf) -> Entry<'n> {
+        let entries = one_of
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::OneOfEntry::Comment(comment) => Entry::Comment(comment),
+                ast::OneOfEntry::Option(option) => self.option(option),
+                ast::OneOfEntry::Field(field) => self.field(field),
+                ast::OneOfEntry::Group(group) => self.group(group),
+            })
+            .collect();
+
+        Entry::Stmt(Stmt {
+            kind: Kind::Block,
+            text: self.block(format!("oneof {}", one_of.ident), &one_of.comments, entries),
+            comments: Some(&one_of.comments),
+        })
+    }
+
+    fn extend<'n>(&self, extend: &'n ast::Extend) -> Entry<'n> {
+        let entries = extend
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::ExtendEntry::Comment(comment) => Entry::Comment(comment),
+                ast::ExtendEntry::Field(field) => self.field(field),
+                ast::ExtendEntry::Group(group) => self.group(group),
+            })
+            .collect();
+
+        Entry::Stmt(Stmt {
+            kind: Kind::Block,
+            text: self.block(
+                format!("extend {}", extend.r#type),
+                &AttachedComments::default(),
+                entries,
+            ),
+            comments: None,
+        })
+    }
+
+    fn r#enum<'n>(&self, r#enum: &'n ast::Enum) -> Entry<'n> {
+        let entries = r#enum
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                ast::EnumEntry::Comment(comment) => Entry::Comment(comment),
+                ast::EnumEntry::Option(option) => self.option(option),
+                ast::EnumEntry::Variant(variant) => Entry::Stmt(Stmt {
+                    kind: Kind::Member,
+                    text: Text::Aligned {
+                        head: variant.ident.to_string(),
+                        tail: format!(
+                            "= {}{};",
+                            variant.value,
+                            self.field_options(&variant.options)
+                        ),
+                    },
+                    comments: Some(&variant.comments),
+                }),
+                ast::EnumEntry::ReservedIndices(indices) => {
+                    member(format!("reserved {};", ranges(indices)))
+                }
+                ast::EnumEntry::ReservedIdents(idents) => member(reserved_idents(idents)),
+            })
+            .collect();
+
+        Entry::Stmt(Stmt {
+            kind: Kind::Block,
+            text: self.block(format!("enum {}", r#enum.ident), &r#enum.comments, entries),
+            comments: Some(&r#enum.comments),
+        })
+    }
+
+    fn field_options(&self, options: &[ast::Option]) -> String {
+        if options.is_empty() {
+            return String::new();
+        }
+
+        let options: Vec<_> = options
+            .iter()
+            .map(|option| format!("{} = {}", option.name, self.value(&option.value)))
+            .collect();
+
+        format!(" [{}]", options.join(", "))
+    }
+
+    fn value(&self, value: &ast::MapValue) -> String {
+        match value {
+            ast::MapValue::Boolean(value) => value.to_string(),
+            ast::MapValue::Integer(value) => value.to_string(),
+            ast::MapValue::Float(value) if value.is_nan() => "nan".to_string(),
+            ast::MapValue::Float(value) if value.is_infinite() => if value.is_sign_positive() {
+                "inf"
+            } else {
+                "-inf"
+            }
+            .to_string(),
+            // Debug formatting always keeps a `.` or an exponent, so the value stays a float.
+            ast::MapValue::Float(value) => format!("{:?}", value),
+            ast::MapValue::Ident(ident) => ident.to_string(),
+            ast::MapValue::String(literal) => string(literal),
+            ast::MapValue::Map(map) if map.is_empty() => "{}".to_string(),
+            ast::MapValue::Map(map) => {
+                let fields: String = map
+                    .iter()
+                    .map(|(key, value)| match value {
+                        ast::MapValue::Map(_) => format!("{} {}\n", key, self.value(value)),
+                        _ => format!("{}: {}\n", key, self.value(value)),
+                    })
+                    .collect();
+
+                format!("{{\n{}}}", self.indent(&fields))
+            }
+            ast::MapValue::List(values) => {
+                let values: Vec<_> = values.iter().map(|value| self.value(value)).collect();
+                format!("[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+/// Returns how many of `comments` make up `text`, the way `protoc` concatenates them.
+fn matching(comments: &[&ast::Comment], text: &str) -> std::option::Option<usize> {
+    let mut matched = String::new();
+
+    for (index, comment) in comments.iter().enumerate() {
+        match comment.r#type {
+            CommentType::SingleLine => {
+                matched.push_str(&comment.source[2..]);
+                matched.push('\n');
+            }
+            CommentType::MultiLine => {
+                matched.push_str(&comments::block_comment_text(&comment.source))
+            }
+        }
+
+        if matched == text {
+            return Some(index + 1);
+        }
+
+        if !text.starts_with(&matched) {
+            break;
+        }
+    }
+
+    None
+}
+
+/// Reindents continuation lines of block comments, keeping their leading `*`.
+fn comment_piece(comment: &ast::Comment) -> Piece {
+    let text = match comment.r#type {
+        CommentType::SingleLine => comment.source.trim_end().to_string(),
+        CommentType::MultiLine => {
+            let mut lines = comment.source.lines();
+            let first = lines.next().unwrap_or_default().trim_end().to_string();
+
+            lines.fold(first, |mut text, line| {
+                let line = line.trim();
+
+                text.push('\n');
+                if line.starts_with('*') {
+                    text.push(' ');
+                }
+                text.push_str(line);
+                text
+            })
+        }
+    };
+
+    Piece::Comment(text)
+}
+
+fn member<'n>(text: String) -> Entry<'n> {
+    Entry::Stmt(Stmt {
+        kind: Kind::Member,
+        text: Text::Plain(text),
+        comments: None,
+    })
+}
+
+fn modifier(modifier: &std::option::Option<ast::FieldModifier>) -> &'static str {
+    match modifier {
+        Some(ast::FieldModifier::Optional) => "optional ",
+        Some(ast::FieldModifier::Required) => "required ",
+        Some(ast::FieldModifier::Repeated) => "repeated ",
+        None => "",
+    }
+}
+
+/// Finite ranges are half-open, so `n..n + 1` is a single number and `a..b` is `a to b - 1`.
+fn ranges(ranges: &[ast::Range]) -> String {
+    let ranges: Vec<_> = ranges
+        .iter()
+        .map(|range| match range {
+            ast::Range::Default(range) if range.end == range.start + 1 => range.start.to_string(),
+            ast::Range::Default(range) => format!("{} to {}", range.start, range.end - 1),
+            ast::Range::From(range) => format!("{} to max", range.start),
+        })
+        .collect();
+
+    ranges.join(", ")
+}
+
+fn reserved_idents(idents: &ast::ReservedIdents) -> String {
+    let idents: Vec<_> = idents.iter().map(string).collect();
+    format!("reserved {};", idents.join(", "))
+}
+
+/// Returns the source of a literal, with adjacent parts separated by a single space.
+fn string(literal: &ast::StringLiteral) -> String {
+    let parts: Vec<_> = lexer::Lexer::new(&literal.raw)
+        .filter_map(|token| match token {
+            Ok((start, lexer::Token::String(_), end)) => Some(&literal.raw[start..end]),
+            _ => None,
+        })
+        .collect();
+
+    match parts.len() {
+        0 | 1 => literal.raw.to_string(),
+        _ => parts.join(" "),
+    }
+}
@@ -0,0 +1,654 @@
+//! Symbol table and resolution of type references across loaded files.
+//!
+//! [`resolve`] collects every package, message, enum, enum value, service, method and
+//! extension declared in a [`FileSet`] and rewrites each type reference to its
+//! fully-qualified form: [`Field::r#type`](ast::Field::r#type) becomes
+//! [`FieldType::Absolute`](ast::FieldType::Absolute), while [`Rpc::request`](ast::Rpc::request),
+//! [`Rpc::reply`](ast::Rpc::reply) and [`Extend::r#type`](ast::Extend::r#type) get a leading `.`.
+//!
+//! Relative names are looked up the way `protoc` does, from the innermost scope outwards. As in
+//! `protoc`, a file only sees its own declarations, those of the files it imports and,
+//! transitively, whatever those files `import public`.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::ast;
+//! use protobuf_ast_parser::loader::Loader;
+//! use protobuf_ast_parser::resolve::{resolve, SymbolKind};
+//! use protobuf_ast_parser::source::Memory;
+//!
+//! let mut memory = Memory::new();
+//! memory.insert("a.proto", "package a; message Outer { message Inner {} Inner inner = 1; }");
+//!
+//! let mut files = Loader::with_provider(memory).load(["a.proto"]).unwrap();
+//! let symbols = resolve(&mut files).unwrap();
+//! assert_eq!(symbols.get("a.Outer.Inner").unwrap().kind, SymbolKind::Message);
+//!
+//! let ast::RootEntry::Message(outer) = &files.get("a.proto").unwrap().ast[1] else {
+//!     unreachable!()
+//! };
+//! let field = outer.fields().next().unwrap();
+//! assert_eq!(field.r#type, ast::FieldType::Absolute("a.Outer.Inner".into()));
+//! ```
+
+use crate::ast::{self, Spanned};
+use crate::loader::FileSet;
+use crate::source::canonical_name;
+use crate::span::{Position, Span};
+use crate::visit::{self, Scope, Visit, VisitMut};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// What a [`Symbol`] declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Package,
+    /// Message, including the message type declared by a `group`.
+    Message,
+    Enum,
+    /// Enum value; following C++ scoping rules, it is named after the scope of its enum.
+    EnumValue,
+    Service,
+    Method,
+    Extension,
+}
+
+impl SymbolKind {
+    /// Returns `true` for kinds a field can have as its type.
+    pub fn is_type(self) -> bool {
+        matches!(self, SymbolKind::Message | SymbolKind::Enum)
+    }
+
+    /// Returns `true` for kinds that contain other symbols.
+    pub fn is_aggregate(self) -> bool {
+        matches!(
+            self,
+            SymbolKind::Package | SymbolKind::Message | SymbolKind::Enum | SymbolKind::Service
+        )
+    }
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            SymbolKind::Package => "package",
+            SymbolKind::Message => "message",
+            SymbolKind::Enum => "enum",
+            SymbolKind::EnumValue => "enum value",
+            SymbolKind::Service => "service",
+            SymbolKind::Method => "method",
+            SymbolKind::Extension => "extension",
+        })
+    }
+}
+
+/// Declaration of a fully-qualified name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    /// Import path of the declaring file; for packages, the first file declaring them.
+    pub file: String,
+    pub span: Span,
+}
+
+/// Every symbol declared by a set of files, keyed by fully-qualified name without the
+/// leading `.`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SymbolTable {
+    symbols: HashMap<String, Symbol>,
+    /// Package declared by each file that has one.
+    packages: HashMap<String, String>,
+    /// Files whose symbols each file can refer to: itself, its imports and, transitively,
+    /// their public imports.
+    visible: HashMap<String, HashSet<String>>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the symbols of `files`, reporting names declared more than once.
+    pub fn build(files: &FileSet) -> Result<Self, Vec<ResolveError>> {
+        let (table, errors) = Self::collect(files);
+
+        match errors.is_empty() {
+            true => Ok(table),
+            false => Err(errors),
+        }
+    }
+
+    /// Collects the symbols of `files`, keeping the first declaration of duplicated names.
+    fn collect(files: &FileSet) -> (Self, Vec<ResolveError>) {
+        let mut table = Self::new();
+        let mut errors = Vec::new();
+
+        for file in files {
+            let mut collector = Collector {
+                table: &mut table,
+                file: &file.name,
+                source: &file.source,
+                errors: &mut errors,
+                extend: false,
+            };
+            collector.visit_root(&file.ast);
+        }
+
+        let mut imports = HashMap::new();
+
+        for file in files {
+            let mut file_imports = Vec::new();
+
+            for entry in &file.ast {
+                match entry {
+                    ast::RootEntry::Package(package) => {
+                        let package = package.path.to_string();
+                        table.packages.insert(file.name.clone(), package);
+                    }
+                    ast::RootEntry::Import(import) => {
+                        let name = canonical_name(&String::from_utf8_lossy(&import.path.value));
+                        file_imports.push((name, import.kind == ast::ImportKind::Public));
+                    }
+                    _ => {}
+                }
+            }
+
+            imports.insert(file.name.as_str(), file_imports);
+        }
+
+        for file in files {
+            let mut visible = HashSet::from([file.name.clone()]);
+
+            for (import, _) in &imports[file.name.as_str()] {
+                export(&imports, import, &mut visible);
+            }
+
+            table.visible.insert(file.name.clone(), visible);
+        }
+
+        (table, errors)
+    }
+
+    /// Returns the symbol with the given fully-qualified name; a leading `.` is ignored.
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Symbol)> {
+        self.symbols
+            .iter()
+            .map(|(name, symbol)| (name.as_str(), symbol))
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Resolves a message or enum reference written in `scope` of `file` to its
+    /// fully-qualified name, among the symbols `file` can see.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use protobuf_ast_parser::loader::Loader;
+    /// use protobuf_ast_parser::resolve::SymbolTable;
+    /// use protobuf_ast_parser::source::Memory;
+    /// use protobuf_ast_parser::visit::Scope;
+    ///
+    /// let mut memory = Memory::new();
+    /// memory.insert("a.proto", "package a.b; message M {} message N { message M {} }");
+    ///
+    /// let files = Loader::with_provider(memory).load(["a.proto"]).unwrap();
+    /// let symbols = SymbolTable::build(&files).unwrap();
+    /// let scope = Scope::package("a.b").child("N");
+    /// assert_eq!(symbols.lookup_type("a.proto", &scope, "M").unwrap(), "a.b.N.M");
+    /// assert_eq!(symbols.lookup_type("a.proto", &scope, "b.M").unwrap(), "a.b.M");
+    /// assert_eq!(symbols.lookup_type("a.proto", &scope, ".a.b.M").unwrap(), "a.b.M");
+    /// ```
+    pub fn lookup_type(
+        &self,
+        file: &str,
+        scope: &Scope,
+        name: &str,
+    ) -> Result<String, ResolveErrorKind> {
+        let name = match self.lookup(Some(file), scope, name) {
+            Err(
+                ResolveErrorKind::Unresolved { name } | ResolveErrorKind::Shadowed { name, .. },
+            ) => name,
+            result => return result,
+        };
+
+        match self.lookup(None, scope, &name) {
+            Ok(resolved) => Err(ResolveErrorKind::NotImported {
+                name,
+                file: self.symbols[&resolved].file.clone(),
+                resolved,
+            }),
+            Err(kind) => Err(kind),
+        }
+    }
+
+    /// Looks `name` up among the symbols `file` can see, or among all of them for `None`.
+    fn lookup(
+        &self,
+        file: Option<&str>,
+        scope: &Scope,
+        name: &str,
+    ) -> Result<String, ResolveErrorKind> {
+        let name: String = name.chars().filter(|ch| !ch.is_whitespace()).collect();
+
+        if let Some(absolute) = name.strip_prefix('.') {
+            return self.expect_type(file, &name, absolute.to_string());
+        }
+
+        let first = name.split('.').next().unwrap_or_default();
+        let segments = scope.segments();
+        // Innermost symbol named `name` that is not a type, reported if no type is found.
+        let mut mismatch = None;
+
+        for depth in (0..=segments.len()).rev() {
+            let prefix = Scope::package(&segments[..depth].join("."));
+            let candidate = prefix.qualify(first);
+
+            let Some(symbol) = self.find(file, &candidate) else {
+                continue;
+            };
+
+            if first.len() < name.len() {
+                // Only the first component was found; the rest must be declared inside it,
+                // and an outer scope is not searched even if it would match.
+                if symbol.kind.is_aggregate() {
+                    let resolved = prefix.qualify(&name);
+
+                    return match self.find(file, &resolved).is_some() {
+                        true => self.expect_type(file, &name, resolved),
+                        false => Err(ResolveErrorKind::Shadowed { name, resolved }),
+                    };
+                }
+            } else if symbol.kind.is_type() {
+                return Ok(candidate);
+            } else if mismatch.is_none() {
+                mismatch = Some((candidate, symbol.kind));
+            }
+        }
+
+        match mismatch {
+            Some((resolved, kind)) => Err(ResolveErrorKind::UnexpectedKind {
+                name,
+                resolved,
+                kind,
+            }),
+            None => Err(ResolveErrorKind::Unresolved { name }),
+        }
+    }
+
+    fn expect_type(
+        &self,
+        file: Option<&str>,
+        name: &str,
+        resolved: String,
+    ) -> Result<String, ResolveErrorKind> {
+        match self.find(file, &resolved) {
+            Some(symbol) if symbol.kind.is_type() => Ok(resolved),
+            Some(symbol) => Err(ResolveErrorKind::UnexpectedKind {
+                name: name.to_string(),
+                kind: symbol.kind,
+                resolved,
+            }),
+            None => Err(ResolveErrorKind::Unresolved {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Returns the symbol `name` if `file` can see it; `None` stands for a file that sees
+    /// everything.
+    fn find(&self, file: Option<&str>, name: &str) -> Option<&Symbol> {
+        let symbol = self.symbols.get(name)?;
+        let Some(file) = file else {
+            return Some(symbol);
+        };
+        let Some(visible) = self.visible.get(file) else {
+            return (symbol.file == file).then_some(symbol);
+        };
+
+        // A package may be declared by several files; it is visible through any of them.
+        let is_visible = match symbol.kind {
+            SymbolKind::Package => visible.iter().any(|file| {
+                self.packages.get(file).is_some_and(|package| {
+                    package == name || package.starts_with(&format!("{}.", name))
+                })
+            }),
+            _ => visible.contains(&symbol.file),
+        };
+
+        is_visible.then_some(symbol)
+    }
+
+    /// Like [`lookup_type`](Self::lookup_type), but the reference must name a message.
+    pub fn lookup_message(
+        &self,
+        file: &str,
+        scope: &Scope,
+        name: &str,
+    ) -> Result<String, ResolveErrorKind> {
+        let resolved = self.lookup_type(file, scope, name)?;
+
+        match self.symbols[&resolved].kind {
+            SymbolKind::Message => Ok(resolved),
+            kind => Err(ResolveErrorKind::UnexpectedKind {
+                name: name.to_string(),
+                resolved,
+                kind,
+            }),
+        }
+    }
+}
+
+/// Adds `file` and, transitively, the files it imports publicly to `visible`.
+fn export(imports: &HashMap<&str, Vec<(String, bool)>>, file: &str, visible: &mut HashSet<String>) {
+    if !visible.insert(file.to_string()) {
+        return;
+    }
+
+    for (import, public) in imports.get(file).into_iter().flatten() {
+        if *public {
+            export(imports, import, visible);
+        }
+    }
+}
+
+/// Builds the symbol table of `files` and rewrites every type reference in them to its
+/// fully-qualified form.
+///
+/// Every error is reported, in file order; references that fail to resolve are left as
+/// written.
+pub fn resolve(files: &mut FileSet) -> Result<SymbolTable, Vec<ResolveError>> {
+    let (table, mut errors) = SymbolTable::collect(files);
+
+    for file in files.iter_mut() {
+        let mut resolver = Resolver {
+            table: &table,
+            file: &file.name,
+            source: &file.source,
+            errors: &mut errors,
+        };
+        resolver.visit_root_mut(&mut file.ast);
+    }
+
+    match errors.is_empty() {
+        true => Ok(table),
+        false => Err(errors),
+    }
+}
+
+/// What went wrong in a [`ResolveError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveErrorKind {
+    /// `name` is declared again; `previous` is the import path of the first declaration.
+    Duplicate { name: String, previous: String },
+    /// No scope declares `name`.
+    Unresolved { name: String },
+    /// The first component of `name` was found in an inner scope, which shadows any outer
+    /// match, but `resolved` does not exist.
+    Shadowed { name: String, resolved: String },
+    /// `name` resolves to a symbol of a kind that cannot be used here.
+    UnexpectedKind {
+        name: String,
+        resolved: String,
+        kind: SymbolKind,
+    },
+    /// `name` resolves to `resolved`, which is declared in `file`, but the referring file does
+    /// not import it.
+    NotImported {
+        name: String,
+        resolved: String,
+        file: String,
+    },
+}
+
+/// Resolution error with its location in the loaded files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveError {
+    pub kind: ResolveErrorKind,
+    /// Import path of the file containing the offending declaration.
+    pub file: String,
+    pub span: Span,
+    pub position: Position,
+}
+
+impl ResolveError {
+    pub(crate) fn new(kind: ResolveErrorKind, file: &str, source: &str, span: Span) -> Self {
+        Self {
+            kind,
+            file: file.to_string(),
+            span,
+            position: span.start_position(source),
+        }
+    }
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.kind {
+            ResolveErrorKind::Duplicate { name, previous } => {
+                write!(f, "\"{}\" is already defined in \"{}\"", name, previous)?
+            }
+            ResolveErrorKind::Unresolved { name } => write!(f, "\"{}\" is not defined", name)?,
+            ResolveErrorKind::Shadowed { name, resolved } => write!(
+                f,
+                "\"{}\" is resolved to \"{}\", which is not defined; the innermost scope is \
+                 searched first, use a leading \".\" to refer to an outer one",
+                name, resolved
+            )?,
+            ResolveErrorKind::UnexpectedKind {
+                name,
+                resolved,
+                kind,
+            } => write!(f, "\"{}\" resolves to {} \"{}\"", name, kind, resolved)?,
+            ResolveErrorKind::NotImported {
+                name,
+                resolved,
+                file,
+            } => write!(
+                f,
+                "\"{}\" resolves to \"{}\", which is declared in \"{}\" but not imported",
+                name, resolved, file
+            )?,
+        }
+
+        write!(f, " at {} {}", self.file, self.position)
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// Fills a [`SymbolTable`] with the declarations of one file.
+struct Collector<'t> {
+    table: &'t mut SymbolTable,
+    file: &'t str,
+    source: &'t str,
+    errors: &'t mut Vec<ResolveError>,
+    /// Whether fields being visited are declared directly in an `extend` block.
+    extend: bool,
+}
+
+impl Collector<'_> {
+    fn add(&mut self, name: String, kind: SymbolKind, span: Span) {
+        match self.table.symbols.get(&name) {
+            None => {
+                self.table.symbols.insert(
+                    name,
+                    Symbol {
+                        kind,
+                        file: self.file.to_string(),
+                        span,
+                    },
+                );
+            }
+            Some(previous) if previous.kind == SymbolKind::Package && kind == previous.kind => {}
+            Some(previous) => {
+                let kind = ResolveErrorKind::Duplicate {
+                    name,
+                    previous: previous.file.clone(),
+                };
+                self.errors
+                    .push(ResolveError::new(kind, self.file, self.source, span));
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Collector<'_> {
+    fn visit_package(&mut self, _scope: &Scope, package: &'ast ast::Package<'ast>) {
+        let mut scope = Scope::new();
+
+        for segment in Scope::package(&package.path).segments() {
+            self.add(scope.qualify(segment), SymbolKind::Package, package.span());
+            scope = scope.child(segment);
+        }
+    }
+
+    fn visit_service(&mut self, scope: &Scope, service: &'ast ast::Service<'ast>) {
+        self.add(
+            scope.qualify(&service.ident),
+            SymbolKind::Service,
+            service.span(),
+        );
+        visit::walk_service(self, scope, service);
+    }
+
+    fn visit_rpc(&mut self, scope: &Scope, rpc: &'ast ast::Rpc<'ast>) {
+        self.add(scope.qualify(&rpc.ident), SymbolKind::Method, rpc.span());
+    }
+
+    fn visit_message(&mut self, scope: &Scope, message: &'ast ast::Message<'ast>) {
+        self.add(
+            scope.qualify(&message.ident),
+            SymbolKind::Message,
+            message.span(),
+        );
+
+        let extend = std::mem::replace(&mut self.extend, false);
+        visit::walk_message(self, scope, message);
+        self.extend = extend;
+    }
+
+    fn visit_field(&mut self, scope: &Scope, field: &'ast ast::Field<'ast>) {
+        if self.extend {
+            self.add(
+                scope.qualify(&field.ident),
+                SymbolKind::Extension,
+                field.span(),
+            );
+        }
+    }
+
+    fn visit_group(&mut self, scope: &Scope, group: &'ast ast::Group<'ast>) {
+        if self.extend {
+            let ident = group.ident.to_ascii_lowercase();
+            self.add(scope.qualify(&ident), SymbolKind::Extension, group.span());
+        }
+
+        self.add(
+            scope.qualify(&group.ident),
+            SymbolKind::Message,
+            group.span(),
+        );
+
+        let extend = std::mem::replace(&mut self.extend, false);
+        visit::walk_group(self, scope, group);
+        self.extend = extend;
+    }
+
+    fn visit_extend(&mut self, scope: &Scope, extend: &'ast ast::Extend<'ast>) {
+        let outer = std::mem::replace(&mut self.extend, true);
+        visit::walk_extend(self, scope, extend);
+        self.extend = outer;
+    }
+
+    fn visit_enum(&mut self, scope: &Scope, r#enum: &'ast ast::Enum<'ast>) {
+        self.add(
+            scope.qualify(&r#enum.ident),
+            SymbolKind::Enum,
+            r#enum.span(),
+        );
+        visit::walk_enum(self, scope, r#enum);
+    }
+
+    fn visit_enum_variant(&mut self, scope: &Scope, variant: &'ast ast::EnumVariant<'ast>) {
+        self.add(
+            scope.qualify(&variant.ident),
+            SymbolKind::EnumValue,
+            variant.span(),
+        );
+    }
+}
+
+/// Rewrites the type references of one file to fully-qualified names.
+struct Resolver<'t> {
+    table: &'t SymbolTable,
+    file: &'t str,
+    source: &'t str,
+    errors: &'t mut Vec<ResolveError>,
+}
+
+impl Resolver<'_> {
+    fn report(&mut self, kind: ResolveErrorKind, span: Span) {
+        self.errors
+            .push(ResolveError::new(kind, self.file, self.source, span));
+    }
+
+    fn field_type(&mut self, scope: &Scope, r#type: &mut ast::FieldType, span: Span) {
+        let name = match r#type {
+            ast::FieldType::Scalar(_) => return,
+            ast::FieldType::Map { key, value } => {
+                self.field_type(scope, key, span);
+                self.field_type(scope, value, span);
+                return;
+            }
+            ast::FieldType::Absolute(name) => format!(".{}", name),
+            ast::FieldType::Relative(name) => name.to_string(),
+        };
+
+        match self.table.lookup_type(self.file, scope, &name) {
+            Ok(resolved) => *r#type = ast::FieldType::Absolute(Cow::Owned(resolved)),
+            Err(kind) => self.report(kind, span),
+        }
+    }
+
+    fn message(&mut self, scope: &Scope, name: &mut Cow<str>, span: Span) {
+        match self.table.lookup_message(self.file, scope, name) {
+            Ok(resolved) => *name = Cow::Owned(format!(".{}", resolved)),
+            Err(kind) => self.report(kind, span),
+        }
+    }
+}
+
+impl VisitMut for Resolver<'_> {
+    fn visit_rpc_mut(&mut self, scope: &Scope, rpc: &mut ast::Rpc) {
+        let span = rpc.span();
+        self.message(scope, &mut rpc.request, span);
+        self.message(scope, &mut rpc.reply, span);
+        visit::walk_rpc_mut(self, scope, rpc);
+    }
+
+    fn visit_field_mut(&mut self, scope: &Scope, field: &mut ast::Field) {
+        let span = field.span();
+        self.field_type(scope, &mut field.r#type, span);
+        visit::walk_field_mut(self, scope, field);
+    }
+
+    fn visit_extend_mut(&mut self, scope: &Scope, extend: &mut ast::Extend) {
+        let span = extend.span();
+        self.message(scope, &mut extend.r#type, span);
+        visit::walk_extend_mut(self, scope, extend);
+    }
+}
@@ -0,0 +1,243 @@
+//! Where the [`Loader`](crate::loader::Loader) reads `.proto` sources from.
+//!
+//! A [`SourceProvider`] maps canonical import paths (such as `google/protobuf/any.proto`) to
+//! source text. [`FileSystem`] searches include directories on disk like `protoc -I`, and
+//! [`Memory`] serves files from a map, which suits virtual file trees, archives and tests.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::source::{Memory, SourceProvider};
+//!
+//! let mut memory = Memory::new();
+//! memory.insert("api/user.proto", "syntax = \"proto3\";");
+//!
+//! let source = memory.read("api/user.proto").unwrap().expect("inserted");
+//! assert_eq!(source.text, "syntax = \"proto3\";");
+//! assert!(memory.read("api/absent.proto").unwrap().is_none());
+//! ```
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Source text of a `.proto` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    /// Location the text was read from, for diagnostics.
+    pub path: PathBuf,
+    pub text: String,
+}
+
+impl Source {
+    pub fn new(path: impl Into<PathBuf>, text: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            text: text.into(),
+        }
+    }
+}
+
+/// Failure to read a file that a [`SourceProvider`] has.
+#[derive(Debug)]
+pub struct ReadError {
+    /// Location the provider tried to read.
+    pub path: PathBuf,
+    pub error: std::io::Error,
+}
+
+impl ReadError {
+    pub fn new(path: impl Into<PathBuf>, error: std::io::Error) -> Self {
+        Self {
+            path: path.into(),
+            error,
+        }
+    }
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Fetches the source of `.proto` files by import path.
+pub trait SourceProvider {
+    /// Returns the file at the canonical import path `name`, or `None` if there is none.
+    /// A file that exists but cannot be read is reported with the location that was tried.
+    fn read(&self, name: &str) -> Result<Option<Source>, ReadError>;
+
+    /// Falls back to `other` for files this provider does not have.
+    fn chain<P: SourceProvider>(self, other: P) -> Chain<Self, P>
+    where
+        Self: Sized,
+    {
+        Chain {
+            first: self,
+            second: other,
+        }
+    }
+}
+
+impl<P: SourceProvider + ?Sized> SourceProvider for &P {
+    fn read(&self, name: &str) -> Result<Option<Source>, ReadError> {
+        (**self).read(name)
+    }
+}
+
+impl<P: SourceProvider + ?Sized> SourceProvider for Box<P> {
+    fn read(&self, name: &str) -> Result<Option<Source>, ReadError> {
+        (**self).read(name)
+    }
+}
+
+/// Provider returned by [`SourceProvider::chain`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: SourceProvider, B: SourceProvider> SourceProvider for Chain<A, B> {
+    fn read(&self, name: &str) -> Result<Option<Source>, ReadError> {
+        match self.first.read(name)? {
+            Some(source) => Ok(Some(source)),
+            None => self.second.read(name),
+        }
+    }
+}
+
+/// Reads files from include directories on disk, searched in order.
+///
+/// Without any include directory, imports are resolved against the current directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileSystem {
+    include_paths: Vec<PathBuf>,
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an include directory.
+    pub fn include(&mut self, path: impl Into<PathBuf>) {
+        self.include_paths.push(path.into());
+    }
+
+    pub fn include_paths(&self) -> &[PathBuf] {
+        &self.include_paths
+    }
+}
+
+impl<P: Into<PathBuf>> FromIterator<P> for FileSystem {
+    fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
+        Self {
+            include_paths: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl SourceProvider for FileSystem {
+    fn read(&self, name: &str) -> Result<Option<Source>, ReadError> {
+        if !is_relative(name) {
+            let error = std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "import path is absolute or contains \"..\"",
+            );
+            return Err(ReadError::new(name, error));
+        }
+
+        let current = [PathBuf::from(".")];
+        let include_paths = match self.include_paths.is_empty() {
+            true => &current[..],
+            false => &self.include_paths[..],
+        };
+
+        for include in include_paths {
+            let path = include.join(name);
+
+            if path.is_file() {
+                let text =
+                    std::fs::read_to_string(&path).map_err(|error| ReadError::new(&path, error))?;
+                return Ok(Some(Source { path, text }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Serves files from memory, keyed by canonical import path.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Memory {
+    files: HashMap<String, String>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the file at `name`.
+    pub fn insert(&mut self, name: impl Into<String>, text: impl Into<String>) {
+        self.files.insert(canonical_name(&name.into()), text.into());
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.files.remove(&canonical_name(name))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.files.get(&canonical_name(name)).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl<N: Into<String>, T: Into<String>> FromIterator<(N, T)> for Memory {
+    fn from_iter<I: IntoIterator<Item = (N, T)>>(iter: I) -> Self {
+        Self {
+            files: iter
+                .into_iter()
+                .map(|(name, text)| (canonical_name(&name.into()), text.into()))
+                .collect(),
+        }
+    }
+}
+
+impl SourceProvider for Memory {
+    fn read(&self, name: &str) -> Result<Option<Source>, ReadError> {
+        Ok(self.get(name).map(|text| Source::new(name, text)))
+    }
+}
+
+/// Whether `name` stays inside the include directories: it must not be absolute (`/a`, `\\a`,
+/// `C:/a`) nor contain `..` segments.
+pub(crate) fn is_relative(name: &str) -> bool {
+    let mut segments = name.split(['/', '\\']);
+    let first = segments.next().unwrap_or_default();
+
+    !first.is_empty()
+        && !first.ends_with(':')
+        && first != ".."
+        && segments.all(|segment| segment != "..")
+}
+
+/// Normalizes an import path: `\` becomes `/`, and empty and `.` segments are dropped.
+pub(crate) fn canonical_name(name: &str) -> String {
+    name.split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,123 @@
+//! Source locations for AST nodes and diagnostics.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::span::{Position, Span};
+//!
+//! let source = "syntax = \"proto3\";\nmessage User {}";
+//! let span = Span::new(19, 34);
+//! assert_eq!(&source[span.range()], "message User {}");
+//! assert_eq!(span.start_position(source), Position::new(2, 1));
+//! ```
+
+/// Half-open byte range (`start..end`) into the parsed source.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the line/column of the first byte of the span.
+    pub fn start_position(&self, source: &str) -> Position {
+        Position::from_offset(source, self.start)
+    }
+
+    /// Returns the line/column just past the last byte of the span.
+    pub fn end_position(&self, source: &str) -> Position {
+        Position::from_offset(source, self.end)
+    }
+}
+
+impl From<std::ops::Range<usize>> for Span {
+    fn from(range: std::ops::Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+impl From<Span> for std::ops::Range<usize> {
+    fn from(span: Span) -> Self {
+        span.range()
+    }
+}
+
+impl ownable::traits::IntoOwned for Span {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
+
+/// One-based line and column (counted in characters) of a byte offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ownable::traits::IntoOwned for Position {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+
+    /// Computes the position of `offset` inside `source`.
+    ///
+    /// Offsets past the end of `source` are clamped to its length.
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Implemented by every AST node that records where it was parsed from.
+///
+/// Nodes built by hand (through `new` constructors) carry [`Span::default`].
+pub trait Spanned {
+    fn span(&self) -> Span;
+
+    fn span_mut(&mut self) -> &mut Span;
+
+    fn with_span(mut self, span: Span) -> Self
+    where
+        Self: Sized,
+    {
+        *self.span_mut() = span;
+        self
+    }
+}
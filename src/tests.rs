@@ -1,5 +1,10 @@
-use crate::{ast, parse};
+use crate::ast::{self, Spanned};
+use crate::{
+    cst, loader, parse, parse_recovering, printer, resolve, source, visit, ParseErrorKind,
+};
+use crate::span::{Position, Span};
 use std::borrow::Cow;
+use std::path::Path;
 
 macro_rules! parse_ast {
     ($file:literal) => {{
@@ -7,11 +12,121 @@ macro_rules! parse_ast {
 
         match parse(&data) {
             Err(error) => panic!("{}", error),
-            Ok(ast) => ast,
+            Ok(mut ast) => {
+                clear_source_info(&mut ast);
+                ast
+            }
         }
     }};
 }
 
+/// Resets every span and attached comment so parsed trees can be compared with hand-built ones.
+fn clear_source_info(root: &mut ast::Root) {
+    fn clear_options(options: &mut [ast::Option]) {
+        options.iter_mut().for_each(|option| option.span = Span::default());
+    }
+
+    fn clear_field(field: &mut ast::Field) {
+        field.span = Span::default();
+        field.comments = Default::default();
+        clear_options(&mut field.options);
+    }
+
+    fn clear_message(message: &mut ast::Message) {
+        message.span = Span::default();
+        message.comments = Default::default();
+        clear_message_entries(&mut message.entries);
+    }
+
+    fn clear_group(group: &mut ast::Group) {
+        group.span = Span::default();
+        group.comments = Default::default();
+        clear_options(&mut group.options);
+        clear_message_entries(&mut group.entries);
+    }
+
+    fn clear_message_entries(entries: &mut [ast::MessageEntry]) {
+        for entry in entries {
+            *entry.span_mut() = Span::default();
+
+            match entry {
+                ast::MessageEntry::Field(field) => clear_field(field),
+                ast::MessageEntry::Group(group) => clear_group(group),
+                ast::MessageEntry::Message(message) => clear_message(message),
+                ast::MessageEntry::Enum(r#enum) => clear_enum(r#enum),
+                ast::MessageEntry::Extend(extend) => clear_extend(extend),
+                ast::MessageEntry::OneOf(one_of) => {
+                    one_of.comments = Default::default();
+
+                    for entry in &mut one_of.entries {
+                        *entry.span_mut() = Span::default();
+
+                        match entry {
+                            ast::OneOfEntry::Field(field) => clear_field(field),
+                            ast::OneOfEntry::Group(group) => clear_group(group),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn clear_extend(extend: &mut ast::Extend) {
+        extend.span = Span::default();
+
+        for entry in &mut extend.entries {
+            *entry.span_mut() = Span::default();
+
+            match entry {
+                ast::ExtendEntry::Field(field) => clear_field(field),
+                ast::ExtendEntry::Group(group) => clear_group(group),
+                _ => {}
+            }
+        }
+    }
+
+    fn clear_enum(r#enum: &mut ast::Enum) {
+        r#enum.span = Span::default();
+        r#enum.comments = Default::default();
+
+        for entry in &mut r#enum.entries {
+            *entry.span_mut() = Span::default();
+
+            if let ast::EnumEntry::Variant(variant) = entry {
+                variant.comments = Default::default();
+                clear_options(&mut variant.options);
+            }
+        }
+    }
+
+    for entry in root {
+        *entry.span_mut() = Span::default();
+
+        match entry {
+            ast::RootEntry::Service(service) => {
+                service.comments = Default::default();
+
+                for entry in &mut service.entries {
+                    *entry.span_mut() = Span::default();
+
+                    if let ast::ServiceEntry::Rpc(rpc) = entry {
+                        rpc.comments = Default::default();
+                        rpc.entries
+                            .iter_mut()
+                            .for_each(|entry| *entry.span_mut() = Span::default());
+                    }
+                }
+            }
+            ast::RootEntry::Message(message) => clear_message(message),
+            ast::RootEntry::Extend(extend) => clear_extend(extend),
+            ast::RootEntry::Enum(r#enum) => clear_enum(r#enum),
+            _ => {}
+        }
+    }
+}
+
 #[test]
 fn empty() {
     let ast = parse_ast!("empty.proto");
@@ -21,17 +136,94 @@ fn empty() {
 #[test]
 fn syntax() {
     let ast = parse_ast!("syntax.proto");
-    let target_ast = vec![ast::RootEntry::Syntax(Cow::from("proto3"))];
+    let target_ast = vec![ast::RootEntry::from(ast::Syntax::new("proto3"))];
+
+    assert_eq!(ast, target_ast);
+}
+
+#[test]
+fn edition() {
+    let ast = parse_ast!("edition.proto");
+    let target_ast = vec![
+        ast::RootEntry::from(ast::Syntax::edition("2023")),
+        ast::RootEntry::from(ast::Package::new("edition")),
+        ast::RootEntry::from(ast::Option::new(
+            "features.field_presence",
+            ast::MapValue::Ident(Cow::from("EXPLICIT")),
+        )),
+        ast::RootEntry::from(ast::Option::new(
+            "features.(pb.cpp).string_type",
+            ast::MapValue::Ident(Cow::from("VIEW")),
+        )),
+        ast::RootEntry::from(ast::Message::new(
+            "Message",
+            vec![
+                ast::MessageEntry::from(ast::Option::new(
+                    "features.message_encoding",
+                    ast::MapValue::Ident(Cow::from("DELIMITED")),
+                )),
+                ast::MessageEntry::from(ast::Field::new(
+                    None,
+                    "int32",
+                    "edition",
+                    1,
+                    vec![ast::Option::new(
+                        "features.field_presence",
+                        ast::MapValue::Ident(Cow::from("IMPLICIT")),
+                    )],
+                )),
+            ],
+        )),
+    ];
 
     assert_eq!(ast, target_ast);
+
+    let ast::RootEntry::Syntax(syntax) = &ast[0] else {
+        panic!("expected syntax, got {:?}", ast[0]);
+    };
+    assert_eq!(syntax.version(), Some(ast::Edition::Edition2023));
+    assert!(syntax.version().is_some_and(|edition| edition.is_edition()));
+
+    let ast::RootEntry::Option(option) = &ast[3] else {
+        panic!("expected option, got {:?}", ast[3]);
+    };
+    assert_eq!(
+        option.feature(),
+        Some(
+            &[
+                ast::OptionNameSegment::Extension(Cow::from("pb.cpp")),
+                ast::OptionNameSegment::Plain(Cow::from("string_type")),
+            ][..]
+        )
+    );
+}
+
+#[test]
+fn error_unknown_syntax() {
+    let error = parse("syntax = \"proto4\";").unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        ParseErrorKind::UnknownSyntax {
+            syntax: "\"proto4\"".into()
+        }
+    );
+    assert_eq!(error.position, Position::new(1, 10));
+
+    let (ast, errors) = parse_recovering("edition = \"2077\";\nmessage Empty {}");
+    assert_eq!(ast.len(), 2);
+    assert_eq!(
+        errors[0].to_string(),
+        "unknown edition \"2077\" at line 1, column 11\n  |\n1 | edition = \"2077\";\n  |           ^^^^^^"
+    );
 }
 
 #[test]
 fn package_simple() {
     let ast = parse_ast!("package-simple.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
-        ast::RootEntry::Package(Cow::from("mypkg")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
+        ast::RootEntry::from(ast::Package::new("mypkg")),
     ];
 
     assert_eq!(ast, target_ast);
@@ -41,8 +233,8 @@ fn package_simple() {
 fn package_complex() {
     let ast = parse_ast!("package-complex.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
-        ast::RootEntry::Package(Cow::from("my.pkg")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
+        ast::RootEntry::from(ast::Package::new("my.pkg")),
     ];
 
     assert_eq!(ast, target_ast);
@@ -52,8 +244,29 @@ fn package_complex() {
 fn import() {
     let ast = parse_ast!("import.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
-        ast::RootEntry::Import(Cow::from("google/protobuf/any.proto")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
+        ast::RootEntry::from(ast::Import::new(
+            ast::ImportKind::Default,
+            "google/protobuf/any.proto",
+        )),
+        ast::RootEntry::from(ast::Import::new(
+            ast::ImportKind::Public,
+            "google/protobuf/timestamp.proto",
+        )),
+        ast::RootEntry::from(ast::Import::new(
+            ast::ImportKind::Weak,
+            "google/protobuf/duration.proto",
+        )),
+        ast::RootEntry::from(ast::Message::empty("public")),
+        ast::RootEntry::from(ast::Message::empty("weak")),
+        ast::RootEntry::from(ast::Message::new(
+            "Message",
+            vec![
+                ast::MessageEntry::from(ast::Field::new(None, "public", "var1", 1, vec![])),
+                ast::MessageEntry::from(ast::Field::new(None, "weak", "var2", 2, vec![])),
+                ast::MessageEntry::from(ast::Field::new(None, "int32", "public", 3, vec![])),
+            ],
+        )),
     ];
 
     assert_eq!(ast, target_ast);
@@ -63,7 +276,7 @@ fn import() {
 fn message_empty() {
     let ast = parse_ast!("message-empty.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
         ast::RootEntry::from(ast::Message::empty("Empty")),
     ];
 
@@ -74,7 +287,7 @@ fn message_empty() {
 fn message() {
     let ast = parse_ast!("message.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
         ast::RootEntry::from(ast::Message::new(
             "Message",
             vec![
@@ -112,11 +325,111 @@ fn message() {
     assert_eq!(ast, target_ast);
 }
 
+#[test]
+fn field_types() {
+    let ast = parse_ast!("field-types.proto");
+
+    let ast::RootEntry::Message(message) = &ast[1] else {
+        panic!("expected message, got {:?}", ast[1]);
+    };
+    let types: Vec<_> = message
+        .entries
+        .iter()
+        .map(|entry| match entry {
+            ast::MessageEntry::Field(field) => &field.r#type,
+            entry => panic!("expected field, got {:?}", entry),
+        })
+        .collect();
+
+    assert_eq!(
+        types,
+        [
+            &ast::FieldType::Scalar(ast::ScalarType::Sint64),
+            &ast::FieldType::Absolute(Cow::from("pkg.Message")),
+            &ast::FieldType::Relative(Cow::from("Message.Inner")),
+            &ast::FieldType::Scalar(ast::ScalarType::Fixed32),
+        ]
+    );
+    assert_eq!(types[1].to_string(), ".pkg.Message");
+
+    let scalar = types[3].scalar().unwrap();
+    assert_eq!(scalar.wire_type(), ast::WireType::Fixed32);
+    assert!(scalar.is_packable());
+    assert!(!ast::ScalarType::Bytes.is_packable());
+    assert_eq!(
+        ast::ScalarType::Bytes.default_value(),
+        ast::MapValue::String(ast::StringLiteral::from(""))
+    );
+}
+
+#[test]
+fn map() {
+    let ast = parse_ast!("map.proto");
+    let target_ast = vec![
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
+        ast::RootEntry::from(ast::Message::new(
+            "Message",
+            vec![
+                ast::MessageEntry::from(ast::Field::new(
+                    None,
+                    ast::FieldType::map(
+                        ast::FieldType::from(ast::ScalarType::String),
+                        ast::FieldType::from(ast::ScalarType::String),
+                    ),
+                    "plain",
+                    1,
+                    vec![],
+                )),
+                ast::MessageEntry::from(ast::Field::new(
+                    None,
+                    "map<int64, pkg.Value>",
+                    "commented",
+                    2,
+                    vec![],
+                )),
+                ast::MessageEntry::from(ast::Field::new(
+                    None,
+                    ast::FieldType::map(
+                        ast::FieldType::from(ast::ScalarType::Bool),
+                        ast::FieldType::Absolute(Cow::from("pkg.Value")),
+                    ),
+                    "multiline",
+                    3,
+                    vec![],
+                )),
+            ],
+        )),
+    ];
+
+    assert_eq!(ast, target_ast);
+}
+
+#[test]
+fn error_invalid_map_key() {
+    let (_, errors) = parse_recovering(
+        "message Message {\n  map<float, string> a = 1;\n  map<Foo, string> b = 2;\n}",
+    );
+    let kinds: Vec<_> = errors.iter().map(|error| &error.kind).collect();
+
+    assert_eq!(
+        kinds,
+        [
+            &ParseErrorKind::InvalidMapKey {
+                r#type: "float".into()
+            },
+            &ParseErrorKind::InvalidMapKey {
+                r#type: "Foo".into()
+            },
+        ]
+    );
+    assert_eq!(errors[0].position, Position::new(2, 7));
+}
+
 #[test]
 fn message_inner() {
     let ast = parse_ast!("message-inner.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
         ast::RootEntry::from(ast::Message::new(
             "Parent",
             vec![
@@ -142,13 +455,21 @@ fn message_inner() {
 fn r#enum() {
     let ast = parse_ast!("enum.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
         ast::RootEntry::from(ast::Enum::new(
             "Enum",
             vec![
                 ast::EnumEntry::from(ast::EnumVariant::new("ZERO", 0, vec![])),
                 ast::EnumEntry::from(ast::EnumVariant::new("POSITIVE", 1, vec![])),
                 ast::EnumEntry::from(ast::EnumVariant::new("NEGATIVE", -1, vec![])),
+                ast::EnumEntry::from(ast::ReservedIndices::from(vec![
+                    ast::Range::from(2..3),
+                    ast::Range::from(15..16),
+                    ast::Range::from(9..12),
+                    ast::Range::from(-10..-4),
+                    ast::Range::from(40..),
+                ])),
+                ast::EnumEntry::from(ast::ReservedIdents::from(vec!["FOO", "BAR"])),
             ],
         )),
     ];
@@ -160,15 +481,18 @@ fn r#enum() {
 fn options() {
     let ast = parse_ast!("options.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
-        ast::RootEntry::Import(Cow::from("google/protobuf/descriptor.proto")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
+        ast::RootEntry::from(ast::Import::new(
+            ast::ImportKind::Default,
+            "google/protobuf/descriptor.proto",
+        )),
         ast::RootEntry::from(ast::Option::new(
             "java_multiple_files",
             ast::MapValue::from(true),
         )),
         ast::RootEntry::from(ast::Option::new(
             "java_package",
-            ast::MapValue::String(Cow::from("xd.xd")),
+            ast::MapValue::String(ast::StringLiteral::from("xd.xd")),
         )),
         ast::RootEntry::from(ast::Extend::new(
             "google.protobuf.EnumValueOptions",
@@ -228,7 +552,10 @@ fn options() {
                                     Cow::from("edition"),
                                     ast::MapValue::Ident(Cow::from("EDITION_PROTO2")),
                                 ),
-                                (Cow::from("value"), ast::MapValue::String(Cow::from("true"))),
+                                (
+                                    Cow::from("value"),
+                                    ast::MapValue::String(ast::StringLiteral::from("true")),
+                                ),
                             ])),
                         ),
                         ast::Option::new(
@@ -240,25 +567,204 @@ fn options() {
                                 ),
                                 (
                                     Cow::from("value"),
-                                    ast::MapValue::String(Cow::from("false")),
+                                    ast::MapValue::String(ast::StringLiteral::from("false")),
                                 ),
                             ])),
                         ),
                     ],
                 )),
+                ast::MessageEntry::from(ast::Field::new(
+                    Some(ast::FieldModifier::Optional),
+                    "string",
+                    "name",
+                    2,
+                    vec![ast::Option::new(
+                        "(validate.rules).string.min_len",
+                        ast::MapValue::Integer(1),
+                    )],
+                )),
+                ast::MessageEntry::from(ast::Option::new(
+                    "(own_message_value).sub_field",
+                    ast::MapValue::Integer(1),
+                )),
             ],
         )),
     ];
 
     assert_eq!(ast, target_ast);
+
+    let ast::RootEntry::Message(message) = &ast[7] else {
+        panic!("expected message, got {:?}", ast[7]);
+    };
+    let ast::MessageEntry::Field(field) = &message.entries[2] else {
+        panic!("expected field, got {:?}", message.entries[2]);
+    };
+    assert_eq!(
+        *field.options[0].name,
+        [
+            ast::OptionNameSegment::Extension(Cow::from("validate.rules")),
+            ast::OptionNameSegment::Plain(Cow::from("string")),
+            ast::OptionNameSegment::Plain(Cow::from("min_len")),
+        ]
+    );
+    assert!(field.options[0].name.is_extension());
+}
+
+#[test]
+fn option_name_spacing() {
+    let ast = parse("option (foo . bar\n .baz).qux = 1;\noption (.foo.bar) = 2;").unwrap();
+    let names: Vec<_> = ast
+        .iter()
+        .filter_map(|entry| match entry {
+            ast::RootEntry::Option(option) => Some(&*option.name),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        *names[0],
+        [
+            ast::OptionNameSegment::Extension(Cow::from("foo.bar.baz")),
+            ast::OptionNameSegment::Plain(Cow::from("qux")),
+        ]
+    );
+    assert_eq!(
+        *names[1],
+        [ast::OptionNameSegment::Extension(Cow::from(".foo.bar"))]
+    );
+}
+
+#[test]
+fn aggregate() {
+    let ast = parse_ast!("aggregate.proto");
+    let target_ast = vec![
+        ast::RootEntry::from(ast::Syntax::new("proto2")),
+        ast::RootEntry::from(ast::Option::new(
+            "(config)",
+            ast::MapValue::from(ast::Map::from([
+                (Cow::from("value"), ast::MapValue::Integer(3)),
+                (
+                    Cow::from("tags"),
+                    ast::MapValue::String(ast::StringLiteral::from("b")),
+                ),
+                (
+                    Cow::from("tags"),
+                    ast::MapValue::String(ast::StringLiteral::from("a")),
+                ),
+                (
+                    Cow::from("nested"),
+                    ast::MapValue::from(ast::Map::from([
+                        (Cow::from("key"), ast::MapValue::Integer(2)),
+                        (Cow::from("key"), ast::MapValue::Integer(1)),
+                    ])),
+                ),
+            ])),
+        )),
+        ast::RootEntry::from(ast::Option::new(
+            "(text_format)",
+            ast::MapValue::from(ast::Map::from([
+                (
+                    Cow::from("angle"),
+                    ast::MapValue::from(ast::Map::from([(
+                        Cow::from("inner"),
+                        ast::MapValue::from(true),
+                    )])),
+                ),
+                (
+                    Cow::from("nested"),
+                    ast::MapValue::from(ast::Map::from([(
+                        Cow::from("value"),
+                        ast::MapValue::Integer(1),
+                    )])),
+                ),
+                (
+                    Cow::from("numbers"),
+                    ast::MapValue::List(vec![
+                        ast::MapValue::Integer(1),
+                        ast::MapValue::Integer(-2),
+                        ast::MapValue::from(3.5),
+                    ]),
+                ),
+                (Cow::from("names"), ast::MapValue::List(vec![])),
+                (
+                    Cow::from("messages"),
+                    ast::MapValue::List(vec![
+                        ast::MapValue::from(ast::Map::from([(
+                            Cow::from("key"),
+                            ast::MapValue::Integer(1),
+                        )])),
+                        ast::MapValue::from(ast::Map::from([(
+                            Cow::from("key"),
+                            ast::MapValue::Integer(2),
+                        )])),
+                    ]),
+                ),
+                (
+                    Cow::from("[my.ext]"),
+                    ast::MapValue::Ident(Cow::from("FOO")),
+                ),
+                (
+                    Cow::from("[type.googleapis.com/pkg.Msg]"),
+                    ast::MapValue::from(ast::Map::from([(
+                        Cow::from("value"),
+                        ast::MapValue::String(ast::StringLiteral::from("any")),
+                    )])),
+                ),
+            ])),
+        )),
+    ];
+
+    assert_eq!(ast, target_ast);
+
+    let ast::RootEntry::Option(option) = &ast[1] else {
+        panic!("expected option, got {:?}", ast[1]);
+    };
+    let ast::MapValue::Map(map) = &option.value else {
+        panic!("expected map, got {:?}", option.value);
+    };
+    assert_eq!(
+        map.keys().collect::<Vec<_>>(),
+        ["value", "tags", "tags", "nested"]
+    );
+    assert_eq!(
+        map.get_all("tags").collect::<Vec<_>>(),
+        [
+            &ast::MapValue::String(ast::StringLiteral::from("b")),
+            &ast::MapValue::String(ast::StringLiteral::from("a")),
+        ]
+    );
+}
+
+#[test]
+fn aggregate_key_spacing() {
+    let ast = parse("option (x) = { [ my . ext ]: 1 [ type.googleapis.com / a.B ] {} };").unwrap();
+
+    let ast::RootEntry::Option(option) = &ast[0] else {
+        panic!("expected option, got {:?}", ast[0]);
+    };
+    let ast::MapValue::Map(map) = &option.value else {
+        panic!("expected map, got {:?}", option.value);
+    };
+    assert_eq!(
+        map.keys().collect::<Vec<_>>(),
+        ["[my.ext]", "[type.googleapis.com/a.B]"]
+    );
+    assert_eq!(map.get("[my.ext]"), Some(&ast::MapValue::Integer(1)));
+    assert_eq!(
+        printer::print(&ast),
+        "option (x) = {\n  [my.ext]: 1\n  [type.googleapis.com/a.B] {}\n};\n"
+    );
 }
 
 #[test]
 fn comments() {
     let ast = parse_ast!("comments.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
-        ast::RootEntry::Import(Cow::from("google/protobuf/descriptor.proto")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
+        ast::RootEntry::from(ast::Import::new(
+            ast::ImportKind::Default,
+            "google/protobuf/descriptor.proto",
+        )),
         ast::RootEntry::from(ast::Comment::single_line("// single line comment")),
         ast::RootEntry::from(ast::Comment::single_line("// another single line comment")),
         ast::RootEntry::from(ast::Comment::multi_line("/* multi\n   line\n   comment */")),
@@ -301,12 +807,12 @@ fn comments() {
 fn extensions() {
     let ast = parse_ast!("extensions.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto2")),
+        ast::RootEntry::from(ast::Syntax::new("proto2")),
         ast::RootEntry::from(ast::Message::new(
             "Message",
             vec![ast::MessageEntry::from(ast::Extensions::from(vec![
                 ast::Range::from(1..2),
-                ast::Range::from(2..5),
+                ast::Range::from(2..6),
                 ast::Range::from(6..),
             ]))],
         )),
@@ -315,11 +821,139 @@ fn extensions() {
     assert_eq!(ast, target_ast);
 }
 
+#[test]
+fn float() {
+    let mut ast = parse_ast!("float.proto");
+
+    let ast::RootEntry::Message(message) = &mut ast[1] else {
+        panic!("expected message, got {:?}", ast[1]);
+    };
+    let Some(ast::MessageEntry::Field(not_a_number)) = message.entries.pop() else {
+        panic!("expected field");
+    };
+    assert!(matches!(
+        not_a_number.options[0].value,
+        ast::MapValue::Float(value) if value.is_nan()
+    ));
+
+    let field = |r#type, ident, index, value: f64| {
+        ast::MessageEntry::from(ast::Field::new(
+            Some(ast::FieldModifier::Optional),
+            r#type,
+            ident,
+            index,
+            vec![ast::Option::new("default", ast::MapValue::from(value))],
+        ))
+    };
+    let target_ast = vec![
+        ast::RootEntry::from(ast::Syntax::new("proto2")),
+        ast::RootEntry::from(ast::Message::new(
+            "Message",
+            vec![
+                field("float", "decimal", 1, 1.5),
+                field("double", "exponent", 2, -2e10),
+                field("double", "leading_dot", 3, 0.25e-3),
+                field("double", "trailing_dot", 4, 10.0),
+                field("double", "positive_inf", 5, f64::INFINITY),
+                field("double", "negative_inf", 6, f64::NEG_INFINITY),
+            ],
+        )),
+    ];
+
+    assert_eq!(ast, target_ast);
+}
+
+#[test]
+fn float_identifiers() {
+    let ast =
+        parse("option a = infinity;\noption b = -infinity;\noption c = -inf;\noption d = -nan;")
+            .unwrap();
+    let values: Vec<_> = ast
+        .iter()
+        .filter_map(|entry| match entry {
+            ast::RootEntry::Option(option) => Some(&option.value),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(values[0], &ast::MapValue::Float(f64::INFINITY));
+    assert_eq!(values[1], &ast::MapValue::Float(f64::NEG_INFINITY));
+    assert_eq!(values[2], &ast::MapValue::Float(f64::NEG_INFINITY));
+    assert!(matches!(values[3], ast::MapValue::Float(value) if value.is_nan()));
+
+    let error = parse("option x = -foo;").unwrap_err();
+    assert_eq!(error.token(), Some("foo"));
+    assert_eq!(error.position.column, 13);
+}
+
+#[test]
+fn integer() {
+    let ast = parse_ast!("integer.proto");
+
+    let field = |r#type, ident, index, value: i128| {
+        ast::MessageEntry::from(ast::Field::new(
+            Some(ast::FieldModifier::Optional),
+            r#type,
+            ident,
+            index,
+            vec![ast::Option::new("default", ast::MapValue::from(value))],
+        ))
+    };
+    let target_ast = vec![
+        ast::RootEntry::from(ast::Syntax::new("proto2")),
+        ast::RootEntry::from(ast::Message::new(
+            "Message",
+            vec![
+                field("uint64", "max_unsigned", 1, u64::MAX.into()),
+                field("int64", "min_signed", 2, i64::MIN.into()),
+                field("int32", "octal", 3, 0o755),
+                field("int32", "hex", 4, 0xFF),
+                field("int32", "negative_hex", 5, -0x10),
+                field("int32", "zero", 6, 0),
+            ],
+        )),
+        ast::RootEntry::from(ast::Enum::new(
+            "Enum",
+            vec![
+                ast::EnumEntry::from(ast::EnumVariant::new("ZERO", 0, vec![])),
+                ast::EnumEntry::from(ast::EnumVariant::new("OCTAL", 8, vec![])),
+                ast::EnumEntry::from(ast::EnumVariant::new("HEX", 0x7FFFFFFF, vec![])),
+                ast::EnumEntry::from(ast::EnumVariant::new("NEGATIVE_HEX", -0x80000000, vec![])),
+            ],
+        )),
+    ];
+
+    assert_eq!(ast, target_ast);
+}
+
+#[test]
+fn integer_invalid() {
+    for (source, column) in [
+        ("option x = 18446744073709551616;", 12),
+        ("option x = 0x10000000000000000;", 12),
+        ("option x = 089;", 12),
+        ("option x = -9223372036854775809;", 12),
+        ("option x = -0x8000000000000001;", 12),
+        ("option x = -18446744073709551615;", 12),
+    ] {
+        let error = parse(source).unwrap_err();
+
+        assert!(
+            matches!(
+                error.kind,
+                ParseErrorKind::Lexical(crate::lexer::LexicalErrorKind::InvalidInteger(_))
+            ),
+            "{source}: {error}"
+        );
+        assert_eq!(error.position.column, column);
+    }
+}
+
 #[test]
 fn required() {
     let ast = parse_ast!("required.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto2")),
+        ast::RootEntry::from(ast::Syntax::new("proto2")),
         ast::RootEntry::from(ast::Message::new(
             "Message",
             vec![ast::MessageEntry::from(ast::Field::new(
@@ -339,7 +973,7 @@ fn required() {
 fn keywords() {
     let ast = parse_ast!("keywords.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
         ast::RootEntry::from(ast::Message::empty("Ident")),
         ast::RootEntry::from(ast::Message::new(
             "to",
@@ -382,7 +1016,7 @@ fn keywords() {
 fn oneof() {
     let ast = parse_ast!("oneof.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
         ast::RootEntry::from(ast::Message::new(
             "Message",
             vec![
@@ -393,7 +1027,7 @@ fn oneof() {
                             "uninterpreted_option",
                             ast::MapValue::from(ast::Map::from([(
                                 Cow::from("string_value"),
-                                ast::MapValue::String(Cow::from("")),
+                                ast::MapValue::String(ast::StringLiteral::from("")),
                             )])),
                         )),
                         ast::OneOfEntry::from(ast::Field::new(
@@ -417,7 +1051,7 @@ fn oneof() {
 fn service() {
     let ast = parse_ast!("service.proto");
     let target_ast = vec![
-        ast::RootEntry::Syntax(Cow::from("proto3")),
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
         ast::RootEntry::from(ast::Service::new(
             "Service",
             vec![
@@ -425,7 +1059,7 @@ fn service() {
                     "uninterpreted_option",
                     ast::MapValue::from(ast::Map::from([(
                         Cow::from("string_value"),
-                        ast::MapValue::String(Cow::from("")),
+                        ast::MapValue::String(ast::StringLiteral::from("")),
                     )])),
                 )),
                 ast::ServiceEntry::from(ast::Rpc::new(
@@ -433,24 +1067,48 @@ fn service() {
                     "Request",
                     "Reply",
                     ast::RpcStream::new(false, false),
+                    vec![],
                 )),
                 ast::ServiceEntry::from(ast::Rpc::new(
                     "RPC2",
                     "Request",
                     "Reply",
                     ast::RpcStream::new(true, false),
+                    vec![],
                 )),
                 ast::ServiceEntry::from(ast::Rpc::new(
                     "RPC3",
                     "Request",
                     "Reply",
                     ast::RpcStream::new(false, true),
+                    vec![],
                 )),
                 ast::ServiceEntry::from(ast::Rpc::new(
                     "RPC4",
                     "Request",
                     "Reply",
                     ast::RpcStream::new(true, true),
+                    vec![],
+                )),
+                ast::ServiceEntry::from(ast::Rpc::new(
+                    "RPC5",
+                    "Request",
+                    "Reply",
+                    ast::RpcStream::new(false, false),
+                    vec![
+                        ast::RpcEntry::from(ast::Comment::single_line("// GET /v1/reply")),
+                        ast::RpcEntry::from(ast::Option::new(
+                            "(google.api.http)",
+                            ast::MapValue::from(ast::Map::from([(
+                                Cow::from("get"),
+                                ast::MapValue::String(ast::StringLiteral::from("/v1/reply")),
+                            )])),
+                        )),
+                        ast::RpcEntry::from(ast::Option::new(
+                            "deprecated",
+                            ast::MapValue::from(true),
+                        )),
+                    ],
                 )),
             ],
         )),
@@ -460,3 +1118,1488 @@ fn service() {
 
     assert_eq!(ast, target_ast);
 }
+
+#[test]
+fn string() {
+    let data = include_str!("../proto/tests/string.proto");
+    let ast = parse(data).expect("valid proto");
+
+    let ast::RootEntry::Import(import) = &ast[1] else {
+        panic!("expected import, got {:?}", ast[1]);
+    };
+    assert_eq!(
+        import.path.as_str(),
+        Some("google/protobuf/descriptor.proto")
+    );
+    assert_eq!(import.path.raw, "\"google/protobuf/\" 'descriptor.proto'");
+
+    let ast::RootEntry::Message(message) = &ast[2] else {
+        panic!("expected message, got {:?}", ast[2]);
+    };
+    let ast::MessageEntry::ReservedIdents(idents) = &message.entries[0] else {
+        panic!("expected reserved idents, got {:?}", message.entries[0]);
+    };
+    let idents: Vec<_> = idents.iter().map(|ident| ident.as_str()).collect();
+    assert_eq!(idents, [Some("foo"), Some("bar")]);
+
+    let defaults: Vec<_> = message.entries[1..]
+        .iter()
+        .map(|entry| match entry {
+            ast::MessageEntry::Field(field) => match &field.options[0].value {
+                ast::MapValue::String(literal) => literal.as_bytes(),
+                value => panic!("expected string, got {:?}", value),
+            },
+            entry => panic!("expected field, got {:?}", entry),
+        })
+        .collect();
+    assert_eq!(
+        defaults,
+        [
+            "a\nb\tAAé\"\\".as_bytes(),
+            b"it's",
+            "é😀".as_bytes(),
+            b"\xe9\xff",
+            b"first second",
+        ]
+    );
+}
+
+#[test]
+fn string_literal_from_str() {
+    let literal = ast::StringLiteral::from("say \"hi\"\n\u{1}");
+
+    assert_eq!(literal.raw, r#""say \"hi\"\n\001""#);
+    assert_eq!(literal.as_str(), Some("say \"hi\"\n\u{1}"));
+    assert_eq!(
+        ast::StringLiteral::from_bytes(b"\xe9").to_string_lossy(),
+        "\u{fffd}"
+    );
+}
+
+#[test]
+fn error_invalid_escape() {
+    let error = parse("option foo = \"bad \\q escape\";").unwrap_err();
+
+    assert_eq!(
+        error.kind,
+        ParseErrorKind::Lexical(crate::lexer::LexicalErrorKind::InvalidEscape)
+    );
+    assert_eq!(error.position, Position::new(1, 14));
+}
+
+#[test]
+fn group() {
+    let ast = parse_ast!("group.proto");
+    let target_ast = vec![
+        ast::RootEntry::from(ast::Syntax::new("proto2")),
+        ast::RootEntry::from(ast::Message::new(
+            "SearchResponse",
+            vec![
+                ast::MessageEntry::from(ast::Group::new(
+                    Some(ast::FieldModifier::Repeated),
+                    "Result",
+                    1,
+                    vec![ast::Option::new("deprecated", ast::MapValue::from(true))],
+                    vec![
+                        ast::MessageEntry::from(ast::Field::new(
+                            Some(ast::FieldModifier::Required),
+                            "string",
+                            "url",
+                            2,
+                            vec![],
+                        )),
+                        ast::MessageEntry::from(ast::Group::new(
+                            Some(ast::FieldModifier::Optional),
+                            "Snippet",
+                            3,
+                            vec![],
+                            vec![ast::MessageEntry::from(ast::Field::new(
+                                Some(ast::FieldModifier::Optional),
+                                "string",
+                                "text",
+                                4,
+                                vec![],
+                            ))],
+                        )),
+                    ],
+                )),
+                ast::MessageEntry::from(ast::OneOf::new(
+                    "kind",
+                    vec![ast::OneOfEntry::from(ast::Group::new(
+                        None,
+                        "Empty",
+                        5,
+                        vec![],
+                        vec![],
+                    ))],
+                )),
+                ast::MessageEntry::from(ast::Extensions::from(vec![ast::Range::from(100..)])),
+            ],
+        )),
+        ast::RootEntry::from(ast::Extend::new(
+            "SearchResponse",
+            vec![ast::ExtendEntry::from(ast::Group::new(
+                Some(ast::FieldModifier::Optional),
+                "Extra",
+                100,
+                vec![],
+                vec![ast::MessageEntry::from(ast::Field::new(
+                    Some(ast::FieldModifier::Optional),
+                    "int32",
+                    "value",
+                    101,
+                    vec![],
+                ))],
+            ))],
+        )),
+    ];
+
+    assert_eq!(ast, target_ast);
+}
+
+#[test]
+fn error_group_type() {
+    let error = parse("message Message {\n  optional Foo Bar = 1 {}\n}").unwrap_err();
+
+    assert_eq!(error.token(), Some("{"));
+    assert_eq!(error.position, Position::new(2, 24));
+}
+
+#[test]
+fn error_group_body() {
+    for (source, column) in [
+        ("message Message {\n  optional group Foo = 1;\n}", 25),
+        ("message Message {\n  group g = 1;\n}", 14),
+    ] {
+        let error = parse(source).unwrap_err();
+
+        assert_eq!(error.token(), Some(";"), "{source}");
+        assert!(error.expected().contains(&"\"{\"".to_string()), "{source}");
+        assert_eq!(error.position, Position::new(2, column), "{source}");
+    }
+}
+
+#[test]
+fn spans() {
+    let data = include_str!("../proto/tests/message-inner.proto");
+    let ast = parse(data).expect("valid proto");
+
+    let ast::RootEntry::Message(parent) = &ast[1] else {
+        panic!("expected message, got {:?}", ast[1]);
+    };
+    assert_eq!(parent.span, Span::new(20, 98));
+    assert_eq!(parent.span.start_position(data), Position::new(3, 1));
+
+    let ast::MessageEntry::Message(child) = &parent.entries[0] else {
+        panic!("expected message, got {:?}", parent.entries[0]);
+    };
+    assert_eq!(&data[child.span.range()], "message Child {\n    bool var = 1;\n  }");
+
+    let ast::MessageEntry::Field(field) = &parent.entries[1] else {
+        panic!("expected field, got {:?}", parent.entries[1]);
+    };
+    assert_eq!(&data[field.span.range()], "Child child = 1;");
+    assert_eq!(field.span.start_position(data), Position::new(8, 3));
+    assert_eq!(field.span.end_position(data), Position::new(8, 19));
+}
+
+#[test]
+fn comments_attached() {
+    let data = include_str!("../proto/tests/comments-attached.proto");
+    let ast = parse(data).expect("valid proto");
+
+    let ast::RootEntry::Message(message) = &ast[2] else {
+        panic!("expected message, got {:?}", ast[2]);
+    };
+    assert_eq!(
+        message.comments,
+        ast::AttachedComments {
+            leading: Some(" Comment attached to Example.\n".to_string()),
+            trailing: Some(" Trailing comment of Example.\n".to_string()),
+            leading_detached: vec![],
+        }
+    );
+
+    let comments: Vec<_> = message
+        .fields()
+        .map(|field| (field.ident.as_ref(), &field.comments))
+        .collect();
+    assert_eq!(
+        comments,
+        [
+            (
+                "foo",
+                &ast::AttachedComments {
+                    trailing: Some(" Comment attached to foo.\n".to_string()),
+                    ..Default::default()
+                }
+            ),
+            (
+                "bar",
+                &ast::AttachedComments {
+                    leading: Some(" Comment attached to bar.\n".to_string()),
+                    ..Default::default()
+                }
+            ),
+            (
+                "baz",
+                &ast::AttachedComments {
+                    trailing: Some(
+                        " Comment attached to baz.\n Another line attached to baz.\n".to_string()
+                    ),
+                    ..Default::default()
+                }
+            ),
+            (
+                "moo",
+                &ast::AttachedComments {
+                    leading: Some(
+                        " Comment attached to moo.\n\n Another line attached to moo.\n".to_string()
+                    ),
+                    ..Default::default()
+                }
+            ),
+            (
+                "corge",
+                &ast::AttachedComments {
+                    trailing: Some(
+                        " Block comment attached\n to corge.  Leading asterisks\n will be removed. "
+                            .to_string()
+                    ),
+                    leading_detached: vec![
+                        " Detached comment for corge. This is not leading or trailing comments\n to moo or corge because there are blank lines separating it from\n both.\n"
+                            .to_string(),
+                        " Detached comment for corge paragraph 2.\n".to_string(),
+                    ],
+                    ..Default::default()
+                }
+            ),
+            (
+                "grault",
+                &ast::AttachedComments {
+                    leading: Some(" Block comment attached to\n grault. ".to_string()),
+                    ..Default::default()
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn error_unrecognized_token() {
+    let error = parse("syntax = \"proto3\";\n\nmessage Message {\n  bool var 1;\n}").unwrap_err();
+
+    assert_eq!(error.position, Position::new(4, 12));
+    assert_eq!(error.source_line, "  bool var 1;");
+    assert_eq!(error.token(), Some("1"));
+    assert_eq!(error.expected(), ["\"=\""]);
+    assert_eq!(
+        error.to_string(),
+        "unexpected \"1\" at line 4, column 12, expected \"=\"\n  |\n4 |   bool var 1;\n  |            ^"
+    );
+}
+
+#[test]
+fn error_unrecognized_eof() {
+    let error = parse("message Message {\n  bool var = 1;").unwrap_err();
+
+    assert!(matches!(error.kind, ParseErrorKind::UnrecognizedEof { .. }));
+    assert_eq!(error.position, Position::new(2, 16));
+    assert!(error.expected().contains(&"\"}\"".to_string()));
+}
+
+#[test]
+fn error_lexical() {
+    let error = parse("message Message {\n  bool var = 99999999999999999999;\n}").unwrap_err();
+
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::Lexical(crate::lexer::LexicalErrorKind::InvalidInteger(_))
+    ));
+    assert_eq!(
+        error.to_string(),
+        "invalid integer (overflow) at line 2, column 14\n  |\n2 |   bool var = 99999999999999999999;\n  |              ^^^^^^^^^^^^^^^^^^^^"
+    );
+}
+
+#[test]
+fn recovering() {
+    let data = r#"syntax = "proto3";
+
+message Message {
+  bool first 1;
+  bool second = 2;
+  bool third = @;
+  message Inner {
+    bool var = 1
+  }
+  bool fourth = 4;
+}
+
+enum Enum { ZERO = 0 }
+"#;
+    let (mut ast, errors) = parse_recovering(data);
+    clear_source_info(&mut ast);
+
+    let positions: Vec<_> = errors.iter().map(|error| error.position).collect();
+    assert_eq!(
+        positions,
+        [
+            Position::new(4, 14),
+            Position::new(6, 16),
+            Position::new(6, 17),
+            Position::new(9, 3),
+            Position::new(13, 22),
+        ]
+    );
+    assert!(matches!(errors[1].kind, ParseErrorKind::Lexical(_)));
+
+    let target_ast = vec![
+        ast::RootEntry::from(ast::Syntax::new("proto3")),
+        ast::RootEntry::from(ast::Message::new(
+            "Message",
+            vec![
+                ast::MessageEntry::from(ast::Field::new(None, "bool", "second", 2, vec![])),
+                ast::MessageEntry::from(ast::Message::empty("Inner")),
+                ast::MessageEntry::from(ast::Field::new(None, "bool", "fourth", 4, vec![])),
+            ],
+        )),
+        ast::RootEntry::from(ast::Enum::new("Enum", vec![])),
+    ];
+
+    assert_eq!(ast, target_ast);
+}
+
+#[test]
+fn recovering_unclosed_block() {
+    let (mut ast, errors) = parse_recovering("message A {}\nmessage B { int32 x = 1;");
+    clear_source_info(&mut ast);
+
+    assert_eq!(
+        ast,
+        vec![
+            ast::RootEntry::from(ast::Message::empty("A")),
+            ast::RootEntry::from(ast::Message::new(
+                "B",
+                vec![ast::MessageEntry::from(ast::Field::new(
+                    None,
+                    "int32",
+                    "x",
+                    1,
+                    vec![],
+                ))],
+            )),
+        ]
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].expected(), ["\"}\""]);
+    assert_eq!(errors[0].position, Position::new(2, 25));
+
+    let (mut ast, errors) = parse_recovering("message B {\n  message C {\n    int32 x = ");
+    clear_source_info(&mut ast);
+
+    assert_eq!(
+        ast,
+        vec![ast::RootEntry::from(ast::Message::new(
+            "B",
+            vec![ast::MessageEntry::from(ast::Message::empty("C"))],
+        ))]
+    );
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0].kind,
+        ParseErrorKind::UnrecognizedEof { .. }
+    ));
+    assert!(parse("message B { int32 x = 1;").is_err());
+}
+
+#[test]
+fn recovering_strict_parse_reports_first_error() {
+    let error = parse("message A { bool a 1; }\nmessage B { bool b 2; }").unwrap_err();
+    assert_eq!(error.position, Position::new(1, 20));
+}
+
+#[test]
+fn print_round_trip() {
+    let sources = [
+        include_str!("../proto/tests/aggregate.proto"),
+        include_str!("../proto/tests/comments-attached.proto"),
+        include_str!("../proto/tests/edition.proto"),
+        include_str!("../proto/tests/enum.proto"),
+        include_str!("../proto/tests/group.proto"),
+        include_str!("../proto/tests/import.proto"),
+        include_str!("../proto/tests/keywords.proto"),
+        include_str!("../proto/tests/map.proto"),
+        include_str!("../proto/tests/message.proto"),
+        include_str!("../proto/tests/options.proto"),
+        include_str!("../proto/tests/service.proto"),
+    ];
+
+    for source in sources {
+        let mut ast = parse(source).expect("valid proto");
+        let printed = printer::print(&ast);
+
+        let mut reparsed = parse(&printed).unwrap_or_else(|error| panic!("{}\n{}", error, printed));
+        assert_eq!(printer::print(&reparsed), printed);
+
+        clear_source_info(&mut ast);
+        clear_source_info(&mut reparsed);
+        assert_eq!(reparsed, ast);
+    }
+}
+
+#[test]
+fn print_ranges() {
+    let source = "syntax = \"proto2\";\n\nmessage Message {\n  reserved 5 to 6, 9;\n  \
+                  extensions 100 to 101, 7;\n}\n\nenum Enum {\n  ZERO = 0;\n  \
+                  reserved 3 to 4, -2 to -1;\n}\n";
+    let ast = parse(source).expect("valid proto");
+
+    assert_eq!(printer::print(&ast), source);
+}
+
+#[test]
+fn print_comments() {
+    let ast = parse_ast!("comments.proto");
+    let options = printer::PrintOptions {
+        comments: false,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        printer::print_with(&ast, &options),
+        r#"syntax = "proto3";
+
+import "google/protobuf/descriptor.proto";
+
+message Message {
+  bool var = 1;
+}
+
+enum Enum {
+  DEFAULT = 0;
+}
+
+extend google.protobuf.FieldOptions {
+  optional bool var = 1;
+}
+"#
+    );
+
+    let data = include_str!("../proto/tests/comments-attached.proto");
+    let ast = parse(data).expect("valid proto");
+    let printed = printer::print(&ast);
+
+    assert_eq!(
+        printed.lines().skip(2).take(9).collect::<Vec<_>>(),
+        [
+            "// Comment attached to Example.",
+            "message Example { // Trailing comment of Example.",
+            "  optional int32 foo = 1; // Comment attached to foo.",
+            "  // Comment attached to bar.",
+            "  optional int32 bar = 2;",
+            "  optional string baz = 3;",
+            "  // Comment attached to baz.",
+            "  // Another line attached to baz.",
+            "",
+        ]
+    );
+    let field_comments = |ast: &ast::Root| -> Vec<ast::AttachedComments> {
+        let ast::RootEntry::Message(message) = &ast[2] else {
+            panic!("expected message, got {:?}", ast[2]);
+        };
+        message
+            .fields()
+            .map(|field| field.comments.clone())
+            .collect()
+    };
+    let reparsed = parse(&printed).expect("valid proto");
+    assert_eq!(field_comments(&reparsed), field_comments(&ast));
+}
+
+#[test]
+fn print_options() {
+    let ast = parse_ast!("enum.proto");
+    let options = printer::PrintOptions {
+        indent: "\t".to_string(),
+        align_field_numbers: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        printer::print_with(&ast, &options),
+        "syntax = \"proto3\";\n\nenum Enum {\n\tZERO     = 0;\n\tPOSITIVE = 1;\n\tNEGATIVE = -1;\n\t\
+         reserved 2, 15, 9 to 11, -10 to -5, 40 to max;\n\treserved \"FOO\", \"BAR\";\n}\n"
+    );
+}
+
+#[test]
+fn cst_lossless() {
+    let sources = [
+        include_str!("../proto/tests/aggregate.proto"),
+        include_str!("../proto/tests/comments.proto"),
+        include_str!("../proto/tests/group.proto"),
+        include_str!("../proto/tests/keywords.proto"),
+        include_str!("../proto/tests/options.proto"),
+        include_str!("../proto/tests/service.proto"),
+        include_str!("../proto/tests/string.proto"),
+        "",
+        "  \n",
+        "syntax = \"proto3\";\r\n;;message A { int32 a = 1 $ ; }\n}\n// no newline",
+    ];
+
+    for source in sources {
+        assert_eq!(cst::SyntaxTree::parse(source).to_string(), source);
+    }
+}
+
+#[test]
+fn cst_nodes() {
+    let data = include_str!("../proto/tests/group.proto");
+    let ast = parse(data).expect("valid proto");
+    let tree = cst::SyntaxTree::parse(data);
+
+    let kinds: Vec<_> = tree.root.nodes().map(|node| node.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            cst::NodeKind::Syntax,
+            cst::NodeKind::Message,
+            cst::NodeKind::Extend,
+        ]
+    );
+
+    let ast::RootEntry::Message(message) = &ast[1] else {
+        panic!("expected message, got {:?}", ast[1]);
+    };
+    let kinds: Vec<_> = message
+        .entries
+        .iter()
+        .map(|entry| tree.find(entry.span()).map(|node| node.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            Some(cst::NodeKind::Group),
+            Some(cst::NodeKind::OneOf),
+            Some(cst::NodeKind::Extensions),
+        ]
+    );
+
+    let ast::RootEntry::Extend(extend) = &ast[2] else {
+        panic!("expected extend, got {:?}", ast[2]);
+    };
+    let extend = tree.find(extend.span).expect("extend node");
+    assert_eq!(
+        extend.nodes().map(|node| node.kind).collect::<Vec<_>>(),
+        [cst::NodeKind::Group, cst::NodeKind::Empty]
+    );
+}
+
+#[test]
+fn cst_keyword_names() {
+    let data = "syntax = \"proto2\";\n\
+                message message {\n\
+                \x20 optional message message = 1;\n\
+                \x20 optional group Group = 2 [(opt) = { a: 1 }] { optional int32 enum = 3; };\n\
+                \x20 enum enum { enum = 0; reserved 1; }\n\
+                \x20 map<string, extend> extend = 4;\n\
+                }\n";
+    let ast = parse(data).expect("valid proto");
+    let tree = cst::SyntaxTree::parse(data);
+
+    let ast::RootEntry::Message(message) = &ast[1] else {
+        panic!("expected message, got {:?}", ast[1]);
+    };
+    let node = tree.find(message.span).expect("message node");
+    assert_eq!(
+        node.nodes().map(|node| node.kind).collect::<Vec<_>>(),
+        [
+            cst::NodeKind::Field,
+            cst::NodeKind::Group,
+            cst::NodeKind::Empty,
+            cst::NodeKind::Enum,
+            cst::NodeKind::Field,
+        ]
+    );
+    assert_eq!(
+        message
+            .entries
+            .iter()
+            .map(|entry| tree.find(entry.span()).map(|node| node.span))
+            .collect::<Vec<_>>(),
+        message
+            .entries
+            .iter()
+            .map(|entry| Some(entry.span()))
+            .collect::<Vec<_>>()
+    );
+
+    let group = node.nodes().nth(1).unwrap();
+    assert_eq!(
+        group.nodes().map(|node| node.kind).collect::<Vec<_>>(),
+        [cst::NodeKind::Field]
+    );
+    let r#enum = node.nodes().nth(3).unwrap();
+    assert_eq!(
+        r#enum.nodes().map(|node| node.kind).collect::<Vec<_>>(),
+        [cst::NodeKind::EnumVariant, cst::NodeKind::Reserved]
+    );
+}
+
+#[test]
+fn cst_trivia() {
+    let data = "message A {\n  int32 a = 1; // first\n  int32 b = 2;\n}\n";
+    let mut tree = cst::SyntaxTree::parse(data);
+
+    let message = tree.root.nodes().next().expect("message node");
+    let field = message.nodes().next().expect("field node");
+    let semicolon = field.tokens().last().expect("semicolon");
+    assert_eq!(
+        semicolon.trailing,
+        [
+            cst::Trivia::new(cst::TriviaKind::Whitespace, " "),
+            cst::Trivia::new(cst::TriviaKind::SingleLineComment, "// first"),
+        ]
+    );
+
+    let next = message.nodes().nth(1).expect("field node");
+    let first = next.tokens().next().expect("type token");
+    assert_eq!(
+        first.leading,
+        [cst::Trivia::new(cst::TriviaKind::Whitespace, "\n  ")]
+    );
+
+    let Some(cst::Element::Node(message)) = tree.root.children.first_mut() else {
+        panic!("expected message node");
+    };
+    let Some(cst::Element::Token(ident)) = message.children.get_mut(1) else {
+        panic!("expected message name");
+    };
+    ident.replace("Renamed");
+    assert_eq!(
+        tree.to_string(),
+        "message Renamed {\n  int32 a = 1; // first\n  int32 b = 2;\n}\n"
+    );
+}
+
+#[test]
+fn visit_scopes() {
+    #[derive(Default)]
+    struct Names(Vec<String>);
+
+    impl<'ast> visit::Visit<'ast> for Names {
+        fn visit_message(&mut self, scope: &visit::Scope, message: &'ast ast::Message<'ast>) {
+            self.0.push(scope.qualify(&message.ident));
+            visit::walk_message(self, scope, message);
+        }
+
+        fn visit_field(&mut self, scope: &visit::Scope, field: &'ast ast::Field<'ast>) {
+            self.0.push(scope.qualify(&field.ident));
+            visit::walk_field(self, scope, field);
+        }
+
+        fn visit_group(&mut self, scope: &visit::Scope, group: &'ast ast::Group<'ast>) {
+            self.0.push(scope.qualify(&group.ident));
+            visit::walk_group(self, scope, group);
+        }
+
+        fn visit_enum_variant(
+            &mut self,
+            scope: &visit::Scope,
+            variant: &'ast ast::EnumVariant<'ast>,
+        ) {
+            self.0.push(scope.qualify(&variant.ident));
+        }
+
+        fn visit_rpc(&mut self, scope: &visit::Scope, rpc: &'ast ast::Rpc<'ast>) {
+            self.0.push(scope.qualify(&rpc.ident));
+        }
+    }
+
+    let data = r#"
+        syntax = "proto2";
+        package a.b;
+
+        message Outer {
+            message Inner { optional int32 value = 1; }
+            enum Kind { KIND_UNSPECIFIED = 0; }
+            oneof choice { string name = 2; }
+            optional group Result = 3 { optional string url = 4; }
+            extend Outer { optional int32 ext = 100; }
+        }
+
+        service Search { rpc Find (Outer) returns (Outer); }
+    "#;
+
+    let ast = parse(data).unwrap();
+    let mut names = Names::default();
+    visit::Visit::visit_root(&mut names, &ast);
+
+    assert_eq!(
+        names.0,
+        [
+            "a.b.Outer",
+            "a.b.Outer.Inner",
+            "a.b.Outer.Inner.value",
+            "a.b.Outer.KIND_UNSPECIFIED",
+            "a.b.Outer.name",
+            "a.b.Outer.Result",
+            "a.b.Outer.Result.url",
+            "a.b.Outer.ext",
+            "a.b.Search.Find",
+        ]
+    );
+}
+
+#[test]
+fn visit_mut() {
+    struct Renumber;
+
+    impl visit::VisitMut for Renumber {
+        fn visit_field_mut(&mut self, scope: &visit::Scope, field: &mut ast::Field) {
+            field.index += 10;
+            visit::walk_field_mut(self, scope, field);
+        }
+
+        fn visit_field_type_mut(&mut self, scope: &visit::Scope, r#type: &mut ast::FieldType) {
+            if let ast::FieldType::Relative(name) = r#type {
+                *r#type = ast::FieldType::Absolute(Cow::Owned(scope.qualify(name)));
+            }
+
+            visit::walk_field_type_mut(self, scope, r#type);
+        }
+    }
+
+    let mut ast = parse("package pkg; message A { B b = 1; map<string, C> c = 2; }").unwrap();
+    visit::VisitMut::visit_root_mut(&mut Renumber, &mut ast);
+
+    assert_eq!(
+        printer::print(&ast),
+        "package pkg;\n\nmessage A {\n  .pkg.A.B b = 11;\n  map<string, .pkg.A.C> c = 12;\n}\n"
+    );
+}
+
+#[test]
+fn load_imports() {
+    let files = loader::Loader::new()
+        .include(concat!(env!("CARGO_MANIFEST_DIR"), "/proto/tests/imports"))
+        .load(["main.proto"])
+        .unwrap();
+
+    assert_eq!(
+        files.names().collect::<Vec<_>>(),
+        ["common/enums.proto", "common/types.proto", "main.proto"]
+    );
+
+    let types = files.get("./common/types.proto").expect("loaded by import");
+    assert!(types.path.ends_with("common/types.proto"));
+    assert!(matches!(
+        &types.ast[1],
+        ast::RootEntry::Package(package) if package.path == "common"
+    ));
+}
+
+#[test]
+fn load_errors() {
+    let loader =
+        loader::Loader::new().include(concat!(env!("CARGO_MANIFEST_DIR"), "/proto/tests/imports"));
+
+    match loader.load(["missing.proto"]) {
+        Err(loader::LoadError::NotFound {
+            name,
+            imported_from: Some(location),
+        }) => {
+            assert_eq!(name, "common/absent.proto");
+            assert_eq!(location.file, "missing.proto");
+            assert_eq!(location.position, Position::new(4, 1));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    match loader.load(["cycle/a.proto"]) {
+        Err(error @ loader::LoadError::Cycle { .. }) => assert_eq!(
+            error.to_string(),
+            "import cycle cycle/a.proto -> cycle/b.proto -> cycle/a.proto \
+             (imported from cycle/b.proto at line 3, column 1)"
+        ),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    assert!(matches!(
+        loader.load(["absent.proto"]),
+        Err(loader::LoadError::NotFound {
+            imported_from: None,
+            ..
+        })
+    ));
+
+    for name in [
+        "../imports/main.proto",
+        "common/../../main.proto",
+        "/etc/passwd",
+        "C:/a.proto",
+    ] {
+        assert!(matches!(
+            loader.load([name]),
+            Err(loader::LoadError::InvalidPath { name: invalid, imported_from: None }) if invalid == name
+        ));
+    }
+
+    let memory: source::Memory = [
+        ("escape.proto", "import \"../secret.proto\";"),
+        ("broken.proto", "message A { bool a 1; }"),
+    ]
+    .into_iter()
+    .collect();
+    let loader = loader::Loader::with_provider(memory);
+
+    match loader.load(["escape.proto"]) {
+        Err(error @ loader::LoadError::InvalidPath { .. }) => assert_eq!(
+            error.to_string(),
+            "import path \"../secret.proto\" leaves the include directories \
+             (imported from escape.proto at line 1, column 1)"
+        ),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    match loader.load(["broken.proto"]) {
+        Err(loader::LoadError::Parse { name, error }) => {
+            assert_eq!(name, "broken.proto");
+            assert_eq!(error.position, Position::new(1, 20));
+            assert_eq!(error.token(), Some("1"));
+            assert_eq!(error.source_line, "message A { bool a 1; }");
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn load_weak_imports() {
+    let memory: source::Memory = [
+        (
+            "main.proto",
+            "import weak \"absent.proto\";\nimport weak \"present.proto\";\nmessage M {}",
+        ),
+        ("present.proto", "import \"missing.proto\";"),
+    ]
+    .into_iter()
+    .collect();
+
+    match loader::Loader::with_provider(memory).load(["main.proto"]) {
+        Err(loader::LoadError::NotFound {
+            name,
+            imported_from: Some(location),
+        }) => {
+            assert_eq!(name, "missing.proto");
+            assert_eq!(location.file, "present.proto");
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let memory: source::Memory = [("main.proto", "import weak \"absent.proto\";\nmessage M {}")]
+        .into_iter()
+        .collect();
+    let files = loader::Loader::with_provider(memory)
+        .load(["main.proto"])
+        .unwrap();
+    assert_eq!(files.names().collect::<Vec<_>>(), ["main.proto"]);
+}
+
+#[test]
+fn load_io_error() {
+    let include = std::env::temp_dir().join(format!("protobuf-io-{}", std::process::id()));
+    std::fs::create_dir_all(&include).unwrap();
+    std::fs::write(include.join("binary.proto"), [0xff, 0xfe]).unwrap();
+
+    let result = loader::Loader::new()
+        .include(&include)
+        .load(["binary.proto"]);
+    std::fs::remove_dir_all(&include).unwrap();
+
+    match result {
+        Err(loader::LoadError::Io { name, path, error }) => {
+            assert_eq!(name, "binary.proto");
+            assert_eq!(path, include.join("binary.proto"));
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let error =
+        source::SourceProvider::read(&source::FileSystem::new(), "../binary.proto").unwrap_err();
+    assert_eq!(error.path, Path::new("../binary.proto"));
+}
+
+#[test]
+fn load_from_memory() {
+    let memory: source::Memory = [
+        (
+            "api/user.proto",
+            "import \"api/./common.proto\";\nmessage User {}",
+        ),
+        ("api/common.proto", "message Common {}"),
+    ]
+    .into_iter()
+    .collect();
+
+    let files = loader::Loader::with_provider(&memory)
+        .load(["api/user.proto"])
+        .unwrap();
+    assert_eq!(
+        files.names().collect::<Vec<_>>(),
+        ["api/common.proto", "api/user.proto"]
+    );
+    assert_eq!(
+        files.get("api/user.proto").unwrap().path,
+        Path::new("api/user.proto")
+    );
+
+    assert!(matches!(
+        loader::Loader::with_provider(memory).load(["api/absent.proto"]),
+        Err(loader::LoadError::NotFound { name, .. }) if name == "api/absent.proto"
+    ));
+}
+
+#[cfg(feature = "well-known-types")]
+#[test]
+fn well_known_types() {
+    let loader = loader::Loader::with_provider(source::Memory::new()).with_well_known_types();
+    let mut files = loader
+        .load(crate::well_known::FILES.iter().map(|(name, _)| name))
+        .unwrap();
+    assert_eq!(files.len(), crate::well_known::FILES.len());
+    resolve::resolve(&mut files).unwrap();
+
+    let files = loader::Loader::new()
+        .include(concat!(env!("CARGO_MANIFEST_DIR"), "/proto/tests"))
+        .with_well_known_types()
+        .load(["options.proto", "comments.proto", "import.proto"])
+        .unwrap();
+    assert_eq!(
+        files.names().collect::<Vec<_>>(),
+        [
+            "google/protobuf/descriptor.proto",
+            "options.proto",
+            "comments.proto",
+            "google/protobuf/any.proto",
+            "google/protobuf/timestamp.proto",
+            "google/protobuf/duration.proto",
+            "import.proto",
+        ]
+    );
+}
+
+#[test]
+fn resolve_names() {
+    let mut files = loader::Loader::new()
+        .include(concat!(env!("CARGO_MANIFEST_DIR"), "/proto/tests"))
+        .load(["resolve/api.proto"])
+        .unwrap();
+    let symbols = resolve::resolve(&mut files).unwrap();
+
+    assert_eq!(
+        symbols
+            .get("corp.api.Request.Page")
+            .map(|symbol| symbol.kind),
+        Some(resolve::SymbolKind::Message)
+    );
+    assert_eq!(
+        symbols.get(".corp.api.request").map(|symbol| symbol.kind),
+        Some(resolve::SymbolKind::Extension)
+    );
+    assert_eq!(
+        symbols
+            .get("corp.common.STATUS_OK")
+            .map(|symbol| symbol.kind),
+        Some(resolve::SymbolKind::EnumValue)
+    );
+
+    let ast = &files.get("resolve/api.proto").unwrap().ast;
+    let absolute = |name: &str| ast::FieldType::Absolute(Cow::Owned(name.to_string()));
+
+    let ast::RootEntry::Message(request) = &ast[3] else {
+        panic!("expected message");
+    };
+    assert_eq!(
+        request
+            .fields()
+            .map(|field| field.r#type.clone())
+            .collect::<Vec<_>>(),
+        [
+            absolute("corp.api.Request.Id"),
+            absolute("corp.common.Id"),
+            absolute("corp.common.Status"),
+            ast::FieldType::Map {
+                key: Box::new(ast::FieldType::Scalar(ast::ScalarType::String)),
+                value: Box::new(absolute("corp.common.Id")),
+            },
+        ]
+    );
+
+    let Some(ast::MessageEntry::Group(page)) = request.entries.last() else {
+        panic!("expected group");
+    };
+    let Some(ast::MessageEntry::Field(id)) = page.entries.first() else {
+        panic!("expected field");
+    };
+    assert_eq!(id.r#type, absolute("corp.api.Request.Id"));
+
+    let ast::RootEntry::Extend(extend) = &ast[4] else {
+        panic!("expected extend");
+    };
+    assert_eq!(extend.r#type, ".corp.common.Base");
+
+    let ast::RootEntry::Service(service) = &ast[5] else {
+        panic!("expected service");
+    };
+    let Some(ast::ServiceEntry::Rpc(rpc)) = service.entries.first() else {
+        panic!("expected rpc");
+    };
+    assert_eq!(
+        (&*rpc.request, &*rpc.reply),
+        (".corp.api.Request", ".corp.common.Id")
+    );
+}
+
+#[test]
+fn resolve_errors() {
+    let mut files = loader::Loader::new()
+        .include(concat!(env!("CARGO_MANIFEST_DIR"), "/proto/tests"))
+        .load(["resolve/errors.proto"])
+        .unwrap();
+    let errors = resolve::resolve(&mut files).unwrap_err();
+
+    assert_eq!(
+        errors.iter().map(|error| &error.kind).collect::<Vec<_>>(),
+        [
+            &resolve::ResolveErrorKind::Duplicate {
+                name: "corp.errors.STATUS_OK".to_string(),
+                previous: "resolve/errors.proto".to_string(),
+            },
+            &resolve::ResolveErrorKind::Unresolved {
+                name: "Missing".to_string(),
+            },
+            &resolve::ResolveErrorKind::Shadowed {
+                name: "common.Id".to_string(),
+                resolved: "corp.errors.Broken.common.Id".to_string(),
+            },
+            &resolve::ResolveErrorKind::UnexpectedKind {
+                name: ".corp.common.STATUS_OK".to_string(),
+                resolved: "corp.common.STATUS_OK".to_string(),
+                kind: resolve::SymbolKind::EnumValue,
+            },
+            &resolve::ResolveErrorKind::UnexpectedKind {
+                name: "common.Status".to_string(),
+                resolved: "corp.common.Status".to_string(),
+                kind: resolve::SymbolKind::Enum,
+            },
+        ]
+    );
+    assert_eq!(
+        errors[1].to_string(),
+        "\"Missing\" is not defined at resolve/errors.proto line 12, column 3"
+    );
+}
+
+#[test]
+fn resolve_unexpected_kind() {
+    let memory: source::Memory = [(
+        "service.proto",
+        "package pkg;\nmessage M {}\nservice S { rpc X(S) returns (M); }",
+    )]
+    .into_iter()
+    .collect();
+    let mut files = loader::Loader::with_provider(memory)
+        .load(["service.proto"])
+        .unwrap();
+    let errors = resolve::resolve(&mut files).unwrap_err();
+
+    assert_eq!(
+        errors.iter().map(|error| &error.kind).collect::<Vec<_>>(),
+        [&resolve::ResolveErrorKind::UnexpectedKind {
+            name: "S".to_string(),
+            resolved: "pkg.S".to_string(),
+            kind: resolve::SymbolKind::Service,
+        }]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "\"S\" resolves to service \"pkg.S\" at service.proto line 3, column 13"
+    );
+}
+
+#[test]
+fn resolve_imports() {
+    let memory: source::Memory = [
+        ("base.proto", "package lib; message Base {}"),
+        (
+            "reexport.proto",
+            "package lib; import public \"base.proto\"; message Middle {}",
+        ),
+        ("hidden.proto", "package hidden; message Secret {}"),
+        ("other.proto", "package other; import \"hidden.proto\";"),
+        (
+            "main.proto",
+            "package app;\n\
+             import \"reexport.proto\";\n\
+             import \"other.proto\";\n\
+             message Main {\n\
+             \x20 lib.Base base = 1;\n\
+             \x20 lib.Middle middle = 2;\n\
+             \x20 hidden.Secret secret = 3;\n\
+             }",
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let mut files = loader::Loader::with_provider(memory)
+        .load(["main.proto"])
+        .unwrap();
+    let errors = resolve::resolve(&mut files).unwrap_err();
+
+    assert_eq!(
+        errors.iter().map(|error| &error.kind).collect::<Vec<_>>(),
+        [&resolve::ResolveErrorKind::NotImported {
+            name: "hidden.Secret".to_string(),
+            resolved: "hidden.Secret".to_string(),
+            file: "hidden.proto".to_string(),
+        }]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "\"hidden.Secret\" resolves to \"hidden.Secret\", which is declared in \"hidden.proto\" \
+         but not imported at main.proto line 7, column 3"
+    );
+
+    let ast::RootEntry::Message(main) = &files.get("main.proto").unwrap().ast[3] else {
+        panic!("expected message");
+    };
+    assert_eq!(
+        main.fields()
+            .map(|field| field.r#type.clone())
+            .take(2)
+            .collect::<Vec<_>>(),
+        [
+            ast::FieldType::Absolute("lib.Base".into()),
+            ast::FieldType::Absolute("lib.Middle".into()),
+        ]
+    );
+}
+
+#[cfg(feature = "descriptor")]
+#[test]
+fn descriptor_set() {
+    use crate::descriptor::{self, prost_types::field_descriptor_proto::Type};
+    use prost::Message;
+
+    let files = loader::Loader::new()
+        .include(concat!(env!("CARGO_MANIFEST_DIR"), "/proto/tests"))
+        .load(["descriptor/user.proto", "resolve/api.proto"])
+        .unwrap();
+    let set = descriptor::file_descriptor_set(&files).unwrap();
+
+    assert_eq!(
+        set.file.iter().map(|file| file.name()).collect::<Vec<_>>(),
+        [
+            "resolve/common.proto",
+            "descriptor/user.proto",
+            "resolve/api.proto"
+        ]
+    );
+
+    let file = &set.file[1];
+    assert_eq!(file.syntax(), "proto3");
+    assert_eq!(file.dependency, ["resolve/common.proto"]);
+    assert_eq!(
+        file.options.as_ref().unwrap().java_package(),
+        "com.corp.users"
+    );
+
+    let user = &file.message_type[0];
+    assert_eq!(
+        user.field
+            .iter()
+            .map(|field| (field.name(), field.json_name(), field.oneof_index))
+            .collect::<Vec<_>>(),
+        [
+            ("display_name", "displayName", None),
+            ("age", "age", Some(1)),
+            ("linked_ids", "linkedIds", None),
+            ("tags", "tags", None),
+            ("email", "email", Some(0)),
+            ("phone", "phone", Some(0)),
+            ("role", "userRole", None),
+        ]
+    );
+    assert!(user.field[1].proto3_optional());
+    assert_eq!(
+        user.oneof_decl
+            .iter()
+            .map(|one_of| one_of.name())
+            .collect::<Vec<_>>(),
+        ["contact", "_age"]
+    );
+    assert_eq!(user.field[2].type_name(), ".corp.users.User.LinkedIdsEntry");
+    assert_eq!(user.nested_type[0].name(), "LinkedIdsEntry");
+    assert!(user.nested_type[0].options.as_ref().unwrap().map_entry());
+    assert_eq!(user.nested_type[0].field[1].type_name(), ".corp.common.Id");
+    assert_eq!(user.field[6].r#type(), Type::Enum);
+    assert_eq!(user.field[6].type_name(), ".corp.users.Role");
+    assert_eq!(
+        user.reserved_range
+            .iter()
+            .map(|range| (range.start(), range.end()))
+            .collect::<Vec<_>>(),
+        [(8, 9), (10, 13), (100, 536_870_912)]
+    );
+
+    let role = &file.enum_type[0];
+    assert_eq!(
+        role.reserved_range
+            .iter()
+            .map(|range| (range.start(), range.end()))
+            .collect::<Vec<_>>(),
+        [(5, 9), (20, i32::MAX)]
+    );
+
+    let watch = &file.service[0].method[1];
+    assert_eq!(watch.input_type(), ".corp.users.User");
+    assert_eq!(watch.output_type(), ".corp.users.User");
+    assert!(watch.client_streaming() && watch.server_streaming());
+
+    let locations = &file.source_code_info.as_ref().unwrap().location;
+    let location = |path: &[i32]| {
+        locations
+            .iter()
+            .find(|location| location.path == path)
+            .unwrap()
+    };
+    assert_eq!(
+        location(&[4, 0]).leading_comments(),
+        " A registered user.\n"
+    );
+    assert_eq!(location(&[4, 0, 2, 0]).span, [17, 2, 26]);
+    assert_eq!(
+        location(&[4, 0, 2, 0]).trailing_comments(),
+        " Shown in the UI.\n"
+    );
+    assert_eq!(
+        location(&[5, 0, 2, 1]).leading_comments(),
+        " Full access.\n"
+    );
+    assert_eq!(location(&[6, 0]).span, [41, 0, 48, 1]);
+
+    let api = &set.file[2];
+    assert_eq!(api.message_type[0].field[4].name(), "page");
+    assert_eq!(api.message_type[0].field[4].r#type(), Type::Group);
+    assert_eq!(
+        api.message_type[0].field[4].type_name(),
+        ".corp.api.Request.Page"
+    );
+    assert_eq!(api.extension[0].extendee(), ".corp.common.Base");
+    assert_eq!(api.extension[0].type_name(), ".corp.api.Request");
+    assert_eq!(api.service[0].method[0].output_type(), ".corp.common.Id");
+
+    let bytes = descriptor::encode(&files).unwrap();
+    assert_eq!(
+        descriptor::prost_types::FileDescriptorSet::decode(&bytes[..]).unwrap(),
+        set
+    );
+}
+
+#[cfg(feature = "descriptor")]
+#[test]
+fn descriptor_options() {
+    use crate::descriptor;
+
+    let memory: source::Memory = [(
+        "options.proto",
+        "syntax = \"proto2\";\n\
+         message Config {\n\
+         \x20 option message_set_wire_format = false;\n\
+         \x20 option (ext.level).limits = { max: 10 nested { on: true } };\n\
+         \x20 extensions 100 to max;\n\
+         \x20 optional bytes key = 1 [default = \"a\\nb\\x01\"];\n\
+         \x20 optional double ratio = 2 [default = -inf, (ext.unit) = -5];\n\
+         \x20 optional Config parent = 3;\n\
+         }",
+    )]
+    .into_iter()
+    .collect();
+    let files = loader::Loader::with_provider(memory)
+        .load(["options.proto"])
+        .unwrap();
+    let set = descriptor::file_descriptor_set(&files).unwrap();
+
+    let config = &set.file[0].message_type[0];
+    let options = config.options.as_ref().unwrap();
+    assert_eq!(options.message_set_wire_format, Some(false));
+    assert_eq!(
+        options.uninterpreted_option[0].name[0].name_part,
+        "ext.level"
+    );
+    assert!(options.uninterpreted_option[0].name[0].is_extension);
+    assert_eq!(options.uninterpreted_option[0].name[1].name_part, "limits");
+    assert_eq!(
+        options.uninterpreted_option[0].aggregate_value(),
+        "max: 10 nested { on: true }"
+    );
+    assert_eq!(config.extension_range[0].end(), 536_870_912);
+
+    assert_eq!(config.field[0].default_value(), "a\\nb\\001");
+    assert_eq!(config.field[1].default_value(), "-inf");
+    assert_eq!(
+        config.field[1]
+            .options
+            .as_ref()
+            .unwrap()
+            .uninterpreted_option[0]
+            .negative_int_value(),
+        -5
+    );
+    assert_eq!(config.field[2].type_name(), ".Config");
+
+    let memory: source::Memory = [("broken.proto", "message A { optional B b = 1; }")]
+        .into_iter()
+        .collect();
+    let files = loader::Loader::with_provider(memory)
+        .load(["broken.proto"])
+        .unwrap();
+    let errors = descriptor::file_descriptor_set(&files).unwrap_err();
+    assert!(matches!(
+        &errors[0],
+        descriptor::DescriptorError::Resolve(error)
+            if error.kind == resolve::ResolveErrorKind::Unresolved { name: "B".to_string() }
+    ));
+}
+
+#[cfg(feature = "descriptor")]
+#[test]
+fn descriptor_float_defaults() {
+    use crate::descriptor;
+
+    let memory: source::Memory = [(
+        "defaults.proto",
+        "syntax = \"proto2\";\n\
+         message Defaults {\n\
+         \x20 optional double large = 1 [default = 1e20];\n\
+         \x20 optional double small = 2 [default = 1e-5];\n\
+         \x20 optional double precise = 3 [default = 123456789.123456789];\n\
+         \x20 optional double fixed = 4 [default = 100000];\n\
+         \x20 optional float tenth = 5 [default = 0.1];\n\
+         \x20 optional float max = 6 [default = 3.4028235e38];\n\
+         \x20 optional float rounded = 7 [default = 16777217];\n\
+         }",
+    )]
+    .into_iter()
+    .collect();
+    let files = loader::Loader::with_provider(memory)
+        .load(["defaults.proto"])
+        .unwrap();
+    let set = descriptor::file_descriptor_set(&files).unwrap();
+
+    let defaults: Vec<_> = set.file[0].message_type[0]
+        .field
+        .iter()
+        .map(|field| field.default_value())
+        .collect();
+    assert_eq!(
+        defaults,
+        [
+            "1e+20",
+            "1e-05",
+            "123456789.12345679",
+            "100000",
+            "0.1",
+            "3.40282347e+38",
+            "16777216"
+        ]
+    );
+}
+
+#[cfg(feature = "descriptor")]
+#[test]
+fn descriptor_numbers() {
+    use crate::descriptor;
+
+    let memory: source::Memory = [
+        (
+            "ranges.proto",
+            "syntax = \"proto2\";\n\
+             message Message {\n\
+             \x20 reserved 5 to 6, 9;\n\
+             \x20 extensions 100 to 101;\n\
+             }\n\
+             enum Enum {\n\
+             \x20 ZERO = 0;\n\
+             \x20 reserved 3 to 4, 7;\n\
+             }\n\
+             extend Message { optional int32 big = 1000000000; }",
+        ),
+        (
+            "invalid.proto",
+            "syntax = \"proto2\";\n\
+             message Message {\n\
+             \x20 optional int32 big = 536870912;\n\
+             \x20 reserved 0, 10 to 4294967296;\n\
+             }\n\
+             enum Enum { TOO_BIG = 2147483648; }",
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let loader = loader::Loader::with_provider(memory);
+
+    let files = loader.load(["ranges.proto"]).unwrap();
+    let set = descriptor::file_descriptor_set(&files).unwrap();
+    let message = &set.file[0].message_type[0];
+    assert_eq!(
+        message
+            .reserved_range
+            .iter()
+            .map(|range| (range.start(), range.end()))
+            .collect::<Vec<_>>(),
+        [(5, 7), (9, 10)]
+    );
+    assert_eq!(
+        (
+            message.extension_range[0].start(),
+            message.extension_range[0].end()
+        ),
+        (100, 102)
+    );
+    assert_eq!(
+        set.file[0].enum_type[0]
+            .reserved_range
+            .iter()
+            .map(|range| (range.start(), range.end()))
+            .collect::<Vec<_>>(),
+        [(3, 4), (7, 7)]
+    );
+    assert_eq!(set.file[0].extension[0].number(), 1_000_000_000);
+
+    let files = loader.load(["invalid.proto"]).unwrap();
+    let errors = descriptor::file_descriptor_set(&files).unwrap_err();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| match error {
+                descriptor::DescriptorError::OutOfRange {
+                    number, min, max, ..
+                } => (*number, *min, *max),
+                error => panic!("unexpected error {}", error),
+            })
+            .collect::<Vec<_>>(),
+        [
+            (536_870_912, 1, 536_870_911),
+            (0, 1, 536_870_911),
+            (4_294_967_296, 1, 536_870_911),
+            (2_147_483_648, i32::MIN, i32::MAX),
+        ]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "536870912 is out of range, expected 1 to 536870911 at invalid.proto line 3, column 3"
+    );
+}
@@ -0,0 +1,920 @@
+//! Traversal of ASTs.
+//!
+//! [`Visit`] walks a tree by reference and [`VisitMut`] by mutable reference. Every method
+//! defaults to the matching `walk_*` function, which visits the children of the node, so an
+//! implementation only overrides the nodes it cares about and calls `walk_*` to keep
+//! descending.
+//!
+//! Each method also receives the [`Scope`] the node is declared in, following protobuf naming:
+//! the package, then the enclosing messages, groups and services. `oneof`, `enum` and `extend`
+//! blocks do not open a scope, since their members are named after the enclosing one.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::{ast, parse};
+//! use protobuf_ast_parser::visit::{self, Scope, Visit};
+//!
+//! #[derive(Default)]
+//! struct Messages(Vec<String>);
+//!
+//! impl<'ast> Visit<'ast> for Messages {
+//!     fn visit_message(&mut self, scope: &Scope, message: &'ast ast::Message<'ast>) {
+//!         self.0.push(scope.qualify(&message.ident));
+//!         visit::walk_message(self, scope, message);
+//!     }
+//! }
+//!
+//! let ast = parse("package pkg; message Outer { message Inner {} }").unwrap();
+//! let mut messages = Messages::default();
+//! messages.visit_root(&ast);
+//! assert_eq!(messages.0, ["pkg.Outer", "pkg.Outer.Inner"]);
+//! ```
+
+use crate::ast;
+
+/// Fully-qualified name of the scope a node is declared in, without the leading `.`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Scope {
+    segments: Vec<String>,
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scope of the top-level declarations of a file in `package`.
+    pub fn package(package: &str) -> Self {
+        Self {
+            segments: package
+                .split('.')
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Scope nested into this one under `name`.
+    pub fn child(&self, name: &str) -> Self {
+        let mut child = self.clone();
+        child.segments.push(name.to_string());
+        child
+    }
+
+    /// Returns the enclosing scope, or `None` at the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.segments.split_last()?;
+
+        Some(Self {
+            segments: parent.to_vec(),
+        })
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the fully-qualified name of `name` declared in this scope.
+    pub fn qualify(&self, name: &str) -> String {
+        match self.is_root() {
+            true => name.to_string(),
+            false => format!("{}.{}", self, name),
+        }
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.segments.join("."))
+    }
+}
+
+/// Read-only traversal of an AST.
+pub trait Visit<'ast> {
+    fn visit_root(&mut self, root: &'ast ast::Root<'ast>) {
+        walk_root(self, root)
+    }
+
+    fn visit_root_entry(&mut self, scope: &Scope, entry: &'ast ast::RootEntry<'ast>) {
+        walk_root_entry(self, scope, entry)
+    }
+
+    fn visit_comment(&mut self, _scope: &Scope, _comment: &'ast ast::Comment<'ast>) {}
+
+    fn visit_syntax(&mut self, scope: &Scope, syntax: &'ast ast::Syntax<'ast>) {
+        walk_syntax(self, scope, syntax)
+    }
+
+    fn visit_package(&mut self, _scope: &Scope, _package: &'ast ast::Package<'ast>) {}
+
+    fn visit_import(&mut self, scope: &Scope, import: &'ast ast::Import<'ast>) {
+        walk_import(self, scope, import)
+    }
+
+    fn visit_option(&mut self, scope: &Scope, option: &'ast ast::Option<'ast>) {
+        walk_option(self, scope, option)
+    }
+
+    fn visit_map_value(&mut self, scope: &Scope, value: &'ast ast::MapValue<'ast>) {
+        walk_map_value(self, scope, value)
+    }
+
+    fn visit_string_literal(&mut self, _scope: &Scope, _literal: &'ast ast::StringLiteral<'ast>) {}
+
+    fn visit_service(&mut self, scope: &Scope, service: &'ast ast::Service<'ast>) {
+        walk_service(self, scope, service)
+    }
+
+    fn visit_service_entry(&mut self, scope: &Scope, entry: &'ast ast::ServiceEntry<'ast>) {
+        walk_service_entry(self, scope, entry)
+    }
+
+    fn visit_rpc(&mut self, scope: &Scope, rpc: &'ast ast::Rpc<'ast>) {
+        walk_rpc(self, scope, rpc)
+    }
+
+    fn visit_rpc_entry(&mut self, scope: &Scope, entry: &'ast ast::RpcEntry<'ast>) {
+        walk_rpc_entry(self, scope, entry)
+    }
+
+    fn visit_message(&mut self, scope: &Scope, message: &'ast ast::Message<'ast>) {
+        walk_message(self, scope, message)
+    }
+
+    fn visit_message_entry(&mut self, scope: &Scope, entry: &'ast ast::MessageEntry<'ast>) {
+        walk_message_entry(self, scope, entry)
+    }
+
+    fn visit_field(&mut self, scope: &Scope, field: &'ast ast::Field<'ast>) {
+        walk_field(self, scope, field)
+    }
+
+    fn visit_field_type(&mut self, scope: &Scope, r#type: &'ast ast::FieldType<'ast>) {
+        walk_field_type(self, scope, r#type)
+    }
+
+    fn visit_group(&mut self, scope: &Scope, group: &'ast ast::Group<'ast>) {
+        walk_group(self, scope, group)
+    }
+
+    fn visit_one_of(&mut self, scope: &Scope, one_of: &'ast ast::OneOf<'ast>) {
+        walk_one_of(self, scope, one_of)
+    }
+
+    fn visit_one_of_entry(&mut self, scope: &Scope, entry: &'ast ast::OneOfEntry<'ast>) {
+        walk_one_of_entry(self, scope, entry)
+    }
+
+    fn visit_extend(&mut self, scope: &Scope, extend: &'ast ast::Extend<'ast>) {
+        walk_extend(self, scope, extend)
+    }
+
+    fn visit_extend_entry(&mut self, scope: &Scope, entry: &'ast ast::ExtendEntry<'ast>) {
+        walk_extend_entry(self, scope, entry)
+    }
+
+    fn visit_enum(&mut self, scope: &Scope, r#enum: &'ast ast::Enum<'ast>) {
+        walk_enum(self, scope, r#enum)
+    }
+
+    fn visit_enum_entry(&mut self, scope: &Scope, entry: &'ast ast::EnumEntry<'ast>) {
+        walk_enum_entry(self, scope, entry)
+    }
+
+    fn visit_enum_variant(&mut self, scope: &Scope, variant: &'ast ast::EnumVariant<'ast>) {
+        walk_enum_variant(self, scope, variant)
+    }
+
+    fn visit_reserved_indices(&mut self, scope: &Scope, indices: &'ast ast::ReservedIndices) {
+        walk_reserved_indices(self, scope, indices)
+    }
+
+    fn visit_reserved_idents(&mut self, scope: &Scope, idents: &'ast ast::ReservedIdents<'ast>) {
+        walk_reserved_idents(self, scope, idents)
+    }
+
+    fn visit_extensions(&mut self, scope: &Scope, extensions: &'ast ast::Extensions) {
+        walk_extensions(self, scope, extensions)
+    }
+
+    fn visit_range(&mut self, _scope: &Scope, _range: &'ast ast::Range) {}
+}
+
+/// Returns the scope of the top-level declarations of `root`.
+pub fn root_scope(root: &ast::Root) -> Scope {
+    root.iter()
+        .find_map(|entry| match entry {
+            ast::RootEntry::Package(package) => Some(Scope::package(&package.path)),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+pub fn walk_root<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, root: &'ast ast::Root<'ast>) {
+    let scope = root_scope(root);
+
+    for entry in root {
+        visitor.visit_root_entry(&scope, entry);
+    }
+}
+
+pub fn walk_root_entry<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &'ast ast::RootEntry<'ast>,
+) {
+    match entry {
+        ast::RootEntry::Comment(comment) => visitor.visit_comment(scope, comment),
+        ast::RootEntry::Syntax(syntax) => visitor.visit_syntax(scope, syntax),
+        ast::RootEntry::Package(package) => visitor.visit_package(scope, package),
+        ast::RootEntry::Import(import) => visitor.visit_import(scope, import),
+        ast::RootEntry::Option(option) => visitor.visit_option(scope, option),
+        ast::RootEntry::Service(service) => visitor.visit_service(scope, service),
+        ast::RootEntry::Message(message) => visitor.visit_message(scope, message),
+        ast::RootEntry::Extend(extend) => visitor.visit_extend(scope, extend),
+        ast::RootEntry::Enum(r#enum) => visitor.visit_enum(scope, r#enum),
+    }
+}
+
+pub fn walk_syntax<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    syntax: &'ast ast::Syntax<'ast>,
+) {
+    visitor.visit_string_literal(scope, &syntax.value);
+}
+
+pub fn walk_import<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    import: &'ast ast::Import<'ast>,
+) {
+    visitor.visit_string_literal(scope, &import.path);
+}
+
+pub fn walk_option<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    option: &'ast ast::Option<'ast>,
+) {
+    visitor.visit_map_value(scope, &option.value);
+}
+
+pub fn walk_map_value<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    value: &'ast ast::MapValue<'ast>,
+) {
+    match value {
+        ast::MapValue::String(literal) => visitor.visit_string_literal(scope, literal),
+        ast::MapValue::Map(map) => {
+            for (_, value) in map.iter() {
+                visitor.visit_map_value(scope, value);
+            }
+        }
+        ast::MapValue::List(values) => {
+            for value in values {
+                visitor.visit_map_value(scope, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn walk_service<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    service: &'ast ast::Service<'ast>,
+) {
+    let scope = scope.child(&service.ident);
+
+    for entry in &service.entries {
+        visitor.visit_service_entry(&scope, entry);
+    }
+}
+
+pub fn walk_service_entry<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &'ast ast::ServiceEntry<'ast>,
+) {
+    match entry {
+        ast::ServiceEntry::Comment(comment) => visitor.visit_comment(scope, comment),
+        ast::ServiceEntry::Option(option) => visitor.visit_option(scope, option),
+        ast::ServiceEntry::Rpc(rpc) => visitor.visit_rpc(scope, rpc),
+    }
+}
+
+pub fn walk_rpc<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    rpc: &'ast ast::Rpc<'ast>,
+) {
+    for entry in &rpc.entries {
+        visitor.visit_rpc_entry(scope, entry);
+    }
+}
+
+pub fn walk_rpc_entry<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &'ast ast::RpcEntry<'ast>,
+) {
+    match entry {
+        ast::RpcEntry::Comment(comment) => visitor.visit_comment(scope, comment),
+        ast::RpcEntry::Option(option) => visitor.visit_option(scope, option),
+    }
+}
+
+pub fn walk_message<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    message: &'ast ast::Message<'ast>,
+) {
+    let scope = scope.child(&message.ident);
+
+    for entry in &message.entries {
+        visitor.visit_message_entry(&scope, entry);
+    }
+}
+
+pub fn walk_message_entry<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &'ast ast::MessageEntry<'ast>,
+) {
+    match entry {
+        ast::MessageEntry::Comment(comment) => visitor.visit_comment(scope, comment),
+        ast::MessageEntry::Option(option) => visitor.visit_option(scope, option),
+        ast::MessageEntry::Field(field) => visitor.visit_field(scope, field),
+        ast::MessageEntry::Group(group) => visitor.visit_group(scope, group),
+        ast::MessageEntry::OneOf(one_of) => visitor.visit_one_of(scope, one_of),
+        ast::MessageEntry::Message(message) => visitor.visit_message(scope, message),
+        ast::MessageEntry::Extend(extend) => visitor.visit_extend(scope, extend),
+        ast::MessageEntry::Enum(r#enum) => visitor.visit_enum(scope, r#enum),
+        ast::MessageEntry::ReservedIndices(indices) => {
+            visitor.visit_reserved_indices(scope, indices)
+        }
+        ast::MessageEntry::ReservedIdents(idents) => visitor.visit_reserved_idents(scope, idents),
+        ast::MessageEntry::Extensions(extensions) => visitor.visit_extensions(scope, extensions),
+    }
+}
+
+pub fn walk_field<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    field: &'ast ast::Field<'ast>,
+) {
+    visitor.visit_field_type(scope, &field.r#type);
+
+    for option in &field.options {
+        visitor.visit_option(scope, option);
+    }
+}
+
+pub fn walk_field_type<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    r#type: &'ast ast::FieldType<'ast>,
+) {
+    if let ast::FieldType::Map { key, value } = r#type {
+        visitor.visit_field_type(scope, key);
+        visitor.visit_field_type(scope, value);
+    }
+}
+
+pub fn walk_group<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    group: &'ast ast::Group<'ast>,
+) {
+    for option in &group.options {
+        visitor.visit_option(scope, option);
+    }
+
+    let scope = scope.child(&group.ident);
+
+    for entry in &group.entries {
+        visitor.visit_message_entry(&scope, entry);
+    }
+}
+
+pub fn walk_one_of<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    one_of: &'ast ast::OneOf<'ast>,
+) {
+    for entry in &one_of.entries {
+        visitor.visit_one_of_entry(scope, entry);
+    }
+}
+
+pub fn walk_one_of_entry<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &'ast ast::OneOfEntry<'ast>,
+) {
+    match entry {
+        ast::OneOfEntry::Comment(comment) => visitor.visit_comment(scope, comment),
+        ast::OneOfEntry::Option(option) => visitor.visit_option(scope, option),
+        ast::OneOfEntry::Field(field) => visitor.visit_field(scope, field),
+        ast::OneOfEntry::Group(group) => visitor.visit_group(scope, group),
+    }
+}
+
+pub fn walk_extend<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    extend: &'ast ast::Extend<'ast>,
+) {
+    for entry in &extend.entries {
+        visitor.visit_extend_entry(scope, entry);
+    }
+}
+
+pub fn walk_extend_entry<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &'ast ast::ExtendEntry<'ast>,
+) {
+    match entry {
+        ast::ExtendEntry::Comment(comment) => visitor.visit_comment(scope, comment),
+        ast::ExtendEntry::Field(field) => visitor.visit_field(scope, field),
+        ast::ExtendEntry::Group(group) => visitor.visit_group(scope, group),
+    }
+}
+
+pub fn walk_enum<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    r#enum: &'ast ast::Enum<'ast>,
+) {
+    for entry in &r#enum.entries {
+        visitor.visit_enum_entry(scope, entry);
+    }
+}
+
+pub fn walk_enum_entry<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &'ast ast::EnumEntry<'ast>,
+) {
+    match entry {
+        ast::EnumEntry::Comment(comment) => visitor.visit_comment(scope, comment),
+        ast::EnumEntry::Option(option) => visitor.visit_option(scope, option),
+        ast::EnumEntry::Variant(variant) => visitor.visit_enum_variant(scope, variant),
+        ast::EnumEntry::ReservedIndices(indices) => visitor.visit_reserved_indices(scope, indices),
+        ast::EnumEntry::ReservedIdents(idents) => visitor.visit_reserved_idents(scope, idents),
+    }
+}
+
+pub fn walk_enum_variant<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    variant: &'ast ast::EnumVariant<'ast>,
+) {
+    for option in &variant.options {
+        visitor.visit_option(scope, option);
+    }
+}
+
+pub fn walk_reserved_indices<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    indices: &'ast ast::ReservedIndices,
+) {
+    for range in indices.iter() {
+        visitor.visit_range(scope, range);
+    }
+}
+
+pub fn walk_reserved_idents<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    idents: &'ast ast::ReservedIdents<'ast>,
+) {
+    for ident in idents.iter() {
+        visitor.visit_string_literal(scope, ident);
+    }
+}
+
+pub fn walk_extensions<'ast, V: Visit<'ast> + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    extensions: &'ast ast::Extensions,
+) {
+    for range in extensions.iter() {
+        visitor.visit_range(scope, range);
+    }
+}
+
+/// Traversal of an AST that can modify it in place.
+pub trait VisitMut {
+    fn visit_root_mut(&mut self, root: &mut ast::Root) {
+        walk_root_mut(self, root)
+    }
+
+    fn visit_root_entry_mut(&mut self, scope: &Scope, entry: &mut ast::RootEntry) {
+        walk_root_entry_mut(self, scope, entry)
+    }
+
+    fn visit_comment_mut(&mut self, _scope: &Scope, _comment: &mut ast::Comment) {}
+
+    fn visit_syntax_mut(&mut self, scope: &Scope, syntax: &mut ast::Syntax) {
+        walk_syntax_mut(self, scope, syntax)
+    }
+
+    fn visit_package_mut(&mut self, _scope: &Scope, _package: &mut ast::Package) {}
+
+    fn visit_import_mut(&mut self, scope: &Scope, import: &mut ast::Import) {
+        walk_import_mut(self, scope, import)
+    }
+
+    fn visit_option_mut(&mut self, scope: &Scope, option: &mut ast::Option) {
+        walk_option_mut(self, scope, option)
+    }
+
+    fn visit_map_value_mut(&mut self, scope: &Scope, value: &mut ast::MapValue) {
+        walk_map_value_mut(self, scope, value)
+    }
+
+    fn visit_string_literal_mut(&mut self, _scope: &Scope, _literal: &mut ast::StringLiteral) {}
+
+    fn visit_service_mut(&mut self, scope: &Scope, service: &mut ast::Service) {
+        walk_service_mut(self, scope, service)
+    }
+
+    fn visit_service_entry_mut(&mut self, scope: &Scope, entry: &mut ast::ServiceEntry) {
+        walk_service_entry_mut(self, scope, entry)
+    }
+
+    fn visit_rpc_mut(&mut self, scope: &Scope, rpc: &mut ast::Rpc) {
+        walk_rpc_mut(self, scope, rpc)
+    }
+
+    fn visit_rpc_entry_mut(&mut self, scope: &Scope, entry: &mut ast::RpcEntry) {
+        walk_rpc_entry_mut(self, scope, entry)
+    }
+
+    fn visit_message_mut(&mut self, scope: &Scope, message: &mut ast::Message) {
+        walk_message_mut(self, scope, message)
+    }
+
+    fn visit_message_entry_mut(&mut self, scope: &Scope, entry: &mut ast::MessageEntry) {
+        walk_message_entry_mut(self, scope, entry)
+    }
+
+    fn visit_field_mut(&mut self, scope: &Scope, field: &mut ast::Field) {
+        walk_field_mut(self, scope, field)
+    }
+
+    fn visit_field_type_mut(&mut self, scope: &Scope, r#type: &mut ast::FieldType) {
+        walk_field_type_mut(self, scope, r#type)
+    }
+
+    fn visit_group_mut(&mut self, scope: &Scope, group: &mut ast::Group) {
+        walk_group_mut(self, scope, group)
+    }
+
+    fn visit_one_of_mut(&mut self, scope: &Scope, one_of: &mut ast::OneOf) {
+        walk_one_of_mut(self, scope, one_of)
+    }
+
+    fn visit_one_of_entry_mut(&mut self, scope: &Scope, entry: &mut ast::OneOfEntry) {
+        walk_one_of_entry_mut(self, scope, entry)
+    }
+
+    fn visit_extend_mut(&mut self, scope: &Scope, extend: &mut ast::Extend) {
+        walk_extend_mut(self, scope, extend)
+    }
+
+    fn visit_extend_entry_mut(&mut self, scope: &Scope, entry: &mut ast::ExtendEntry) {
+        walk_extend_entry_mut(self, scope, entry)
+    }
+
+    fn visit_enum_mut(&mut self, scope: &Scope, r#enum: &mut ast::Enum) {
+        walk_enum_mut(self, scope, r#enum)
+    }
+
+    fn visit_enum_entry_mut(&mut self, scope: &Scope, entry: &mut ast::EnumEntry) {
+        walk_enum_entry_mut(self, scope, entry)
+    }
+
+    fn visit_enum_variant_mut(&mut self, scope: &Scope, variant: &mut ast::EnumVariant) {
+        walk_enum_variant_mut(self, scope, variant)
+    }
+
+    fn visit_reserved_indices_mut(&mut self, scope: &Scope, indices: &mut ast::ReservedIndices) {
+        walk_reserved_indices_mut(self, scope, indices)
+    }
+
+    fn visit_reserved_idents_mut(&mut self, scope: &Scope, idents: &mut ast::ReservedIdents) {
+        walk_reserved_idents_mut(self, scope, idents)
+    }
+
+    fn visit_extensions_mut(&mut self, scope: &Scope, extensions: &mut ast::Extensions) {
+        walk_extensions_mut(self, scope, extensions)
+    }
+
+    fn visit_range_mut(&mut self, _scope: &Scope, _range: &mut ast::Range) {}
+}
+
+pub fn walk_root_mut<V: VisitMut + ?Sized>(visitor: &mut V, root: &mut ast::Root) {
+    let scope = root_scope(root);
+
+    for entry in root {
+        visitor.visit_root_entry_mut(&scope, entry);
+    }
+}
+
+pub fn walk_root_entry_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &mut ast::RootEntry,
+) {
+    match entry {
+        ast::RootEntry::Comment(comment) => visitor.visit_comment_mut(scope, comment),
+        ast::RootEntry::Syntax(syntax) => visitor.visit_syntax_mut(scope, syntax),
+        ast::RootEntry::Package(package) => visitor.visit_package_mut(scope, package),
+        ast::RootEntry::Import(import) => visitor.visit_import_mut(scope, import),
+        ast::RootEntry::Option(option) => visitor.visit_option_mut(scope, option),
+        ast::RootEntry::Service(service) => visitor.visit_service_mut(scope, service),
+        ast::RootEntry::Message(message) => visitor.visit_message_mut(scope, message),
+        ast::RootEntry::Extend(extend) => visitor.visit_extend_mut(scope, extend),
+        ast::RootEntry::Enum(r#enum) => visitor.visit_enum_mut(scope, r#enum),
+    }
+}
+
+pub fn walk_syntax_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    syntax: &mut ast::Syntax,
+) {
+    visitor.visit_string_literal_mut(scope, &mut syntax.value);
+}
+
+pub fn walk_import_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    import: &mut ast::Import,
+) {
+    visitor.visit_string_literal_mut(scope, &mut import.path);
+}
+
+pub fn walk_option_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    option: &mut ast::Option,
+) {
+    visitor.visit_map_value_mut(scope, &mut option.value);
+}
+
+pub fn walk_map_value_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    value: &mut ast::MapValue,
+) {
+    match value {
+        ast::MapValue::String(literal) => visitor.visit_string_literal_mut(scope, literal),
+        ast::MapValue::Map(map) => {
+            for (_, value) in map.iter_mut() {
+                visitor.visit_map_value_mut(scope, value);
+            }
+        }
+        ast::MapValue::List(values) => {
+            for value in values {
+                visitor.visit_map_value_mut(scope, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn walk_service_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    service: &mut ast::Service,
+) {
+    let scope = scope.child(&service.ident);
+
+    for entry in &mut service.entries {
+        visitor.visit_service_entry_mut(&scope, entry);
+    }
+}
+
+pub fn walk_service_entry_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &mut ast::ServiceEntry,
+) {
+    match entry {
+        ast::ServiceEntry::Comment(comment) => visitor.visit_comment_mut(scope, comment),
+        ast::ServiceEntry::Option(option) => visitor.visit_option_mut(scope, option),
+        ast::ServiceEntry::Rpc(rpc) => visitor.visit_rpc_mut(scope, rpc),
+    }
+}
+
+pub fn walk_rpc_mut<V: VisitMut + ?Sized>(visitor: &mut V, scope: &Scope, rpc: &mut ast::Rpc) {
+    for entry in &mut rpc.entries {
+        visitor.visit_rpc_entry_mut(scope, entry);
+    }
+}
+
+pub fn walk_rpc_entry_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &mut ast::RpcEntry,
+) {
+    match entry {
+        ast::RpcEntry::Comment(comment) => visitor.visit_comment_mut(scope, comment),
+        ast::RpcEntry::Option(option) => visitor.visit_option_mut(scope, option),
+    }
+}
+
+pub fn walk_message_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    message: &mut ast::Message,
+) {
+    let scope = scope.child(&message.ident);
+
+    for entry in &mut message.entries {
+        visitor.visit_message_entry_mut(&scope, entry);
+    }
+}
+
+pub fn walk_message_entry_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &mut ast::MessageEntry,
+) {
+    match entry {
+        ast::MessageEntry::Comment(comment) => visitor.visit_comment_mut(scope, comment),
+        ast::MessageEntry::Option(option) => visitor.visit_option_mut(scope, option),
+        ast::MessageEntry::Field(field) => visitor.visit_field_mut(scope, field),
+        ast::MessageEntry::Group(group) => visitor.visit_group_mut(scope, group),
+        ast::MessageEntry::OneOf(one_of) => visitor.visit_one_of_mut(scope, one_of),
+        ast::MessageEntry::Message(message) => visitor.visit_message_mut(scope, message),
+        ast::MessageEntry::Extend(extend) => visitor.visit_extend_mut(scope, extend),
+        ast::MessageEntry::Enum(r#enum) => visitor.visit_enum_mut(scope, r#enum),
+        ast::MessageEntry::ReservedIndices(indices) => {
+            visitor.visit_reserved_indices_mut(scope, indices)
+        }
+        ast::MessageEntry::ReservedIdents(idents) => {
+            visitor.visit_reserved_idents_mut(scope, idents)
+        }
+        ast::MessageEntry::Extensions(extensions) => {
+            visitor.visit_extensions_mut(scope, extensions)
+        }
+    }
+}
+
+pub fn walk_field_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    field: &mut ast::Field,
+) {
+    visitor.visit_field_type_mut(scope, &mut field.r#type);
+
+    for option in &mut field.options {
+        visitor.visit_option_mut(scope, option);
+    }
+}
+
+pub fn walk_field_type_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    r#type: &mut ast::FieldType,
+) {
+    if let ast::FieldType::Map { key, value } = r#type {
+        visitor.visit_field_type_mut(scope, key);
+        visitor.visit_field_type_mut(scope, value);
+    }
+}
+
+pub fn walk_group_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    group: &mut ast::Group,
+) {
+    for option in &mut group.options {
+        visitor.visit_option_mut(scope, option);
+    }
+
+    let scope = scope.child(&group.ident);
+
+    for entry in &mut group.entries {
+        visitor.visit_message_entry_mut(&scope, entry);
+    }
+}
+
+pub fn walk_one_of_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    one_of: &mut ast::OneOf,
+) {
+    for entry in &mut one_of.entries {
+        visitor.visit_one_of_entry_mut(scope, entry);
+    }
+}
+
+pub fn walk_one_of_entry_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &mut ast::OneOfEntry,
+) {
+    match entry {
+        ast::OneOfEntry::Comment(comment) => visitor.visit_comment_mut(scope, comment),
+        ast::OneOfEntry::Option(option) => visitor.visit_option_mut(scope, option),
+        ast::OneOfEntry::Field(field) => visitor.visit_field_mut(scope, field),
+        ast::OneOfEntry::Group(group) => visitor.visit_group_mut(scope, group),
+    }
+}
+
+pub fn walk_extend_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    extend: &mut ast::Extend,
+) {
+    for entry in &mut extend.entries {
+        visitor.visit_extend_entry_mut(scope, entry);
+    }
+}
+
+pub fn walk_extend_entry_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &mut ast::ExtendEntry,
+) {
+    match entry {
+        ast::ExtendEntry::Comment(comment) => visitor.visit_comment_mut(scope, comment),
+        ast::ExtendEntry::Field(field) => visitor.visit_field_mut(scope, field),
+        ast::ExtendEntry::Group(group) => visitor.visit_group_mut(scope, group),
+    }
+}
+
+pub fn walk_enum_mut<V: VisitMut + ?Sized>(visitor: &mut V, scope: &Scope, r#enum: &mut ast::Enum) {
+    for entry in &mut r#enum.entries {
+        visitor.visit_enum_entry_mut(scope, entry);
+    }
+}
+
+pub fn walk_enum_entry_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    entry: &mut ast::EnumEntry,
+) {
+    match entry {
+        ast::EnumEntry::Comment(comment) => visitor.visit_comment_mut(scope, comment),
+        ast::EnumEntry::Option(option) => visitor.visit_option_mut(scope, option),
+        ast::EnumEntry::Variant(variant) => visitor.visit_enum_variant_mut(scope, variant),
+        ast::EnumEntry::ReservedIndices(indices) => {
+            visitor.visit_reserved_indices_mut(scope, indices)
+        }
+        ast::EnumEntry::ReservedIdents(idents) => visitor.visit_reserved_idents_mut(scope, idents),
+    }
+}
+
+pub fn walk_enum_variant_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    variant: &mut ast::EnumVariant,
+) {
+    for option in &mut variant.options {
+        visitor.visit_option_mut(scope, option);
+    }
+}
+
+pub fn walk_reserved_indices_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    indices: &mut ast::ReservedIndices,
+) {
+    for range in indices.iter_mut() {
+        visitor.visit_range_mut(scope, range);
+    }
+}
+
+pub fn walk_reserved_idents_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    idents: &mut ast::ReservedIdents,
+) {
+    for ident in idents.iter_mut() {
+        visitor.visit_string_literal_mut(scope, ident);
+    }
+}
+
+pub fn walk_extensions_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    scope: &Scope,
+    extensions: &mut ast::Extensions,
+) {
+    for range in extensions.iter_mut() {
+        visitor.visit_range_mut(scope, range);
+    }
+}
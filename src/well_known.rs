@@ -0,0 +1,69 @@
+//! Bundled `google/protobuf/*.proto` files (the well-known types), available with the
+//! `well-known-types` feature.
+//!
+//! The sources are taken from protobuf 21.5 and embedded in the binary, so imports such as
+//! `google/protobuf/descriptor.proto` resolve without vendoring them.
+//!
+//! # Examples
+//! ```rust
+//! use protobuf_ast_parser::loader::Loader;
+//! use protobuf_ast_parser::source::Memory;
+//!
+//! let mut memory = Memory::new();
+//! memory.insert(
+//!     "event.proto",
+//!     "import \"google/protobuf/timestamp.proto\";\n\
+//!      message Event { google.protobuf.Timestamp at = 1; }",
+//! );
+//!
+//! let files = Loader::with_provider(memory)
+//!     .with_well_known_types()
+//!     .load(["event.proto"])
+//!     .unwrap();
+//! assert!(files.contains("google/protobuf/timestamp.proto"));
+//! ```
+
+use crate::source::{ReadError, Source, SourceProvider};
+
+macro_rules! files {
+    ($($name:literal),* $(,)?) => {
+        &[$((
+            concat!("google/protobuf/", $name),
+            include_str!(concat!("../proto/google/protobuf/", $name)),
+        )),*]
+    };
+}
+
+/// Import path and source text of every bundled file.
+pub const FILES: &[(&str, &str)] = files![
+    "any.proto",
+    "api.proto",
+    "compiler/plugin.proto",
+    "descriptor.proto",
+    "duration.proto",
+    "empty.proto",
+    "field_mask.proto",
+    "source_context.proto",
+    "struct.proto",
+    "timestamp.proto",
+    "type.proto",
+    "wrappers.proto",
+];
+
+/// Returns the source of a bundled file by import path.
+pub fn get(name: &str) -> Option<&'static str> {
+    FILES
+        .iter()
+        .find(|(file, _)| *file == name)
+        .map(|(_, source)| *source)
+}
+
+/// [`SourceProvider`] serving the bundled files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WellKnownTypes;
+
+impl SourceProvider for WellKnownTypes {
+    fn read(&self, name: &str) -> Result<Option<Source>, ReadError> {
+        Ok(get(name).map(|text| Source::new(name, text)))
+    }
+}